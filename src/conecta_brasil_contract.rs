@@ -1,10 +1,52 @@
-use crate::model::{Access, DataKey, Error, OrderRec, OrderSession, Package, Session};
+use crate::model::{
+    Access, Coupon, CouponDiscount, CouponRules, DataKey, Error, LoyaltyAccount, LoyaltyConfig, LoyaltyTier, Locale, Operation, OracleConfig, OracleQuote, OrderRec, OrderSession,
+    OrderStatus, Package, PackageLimits,
+    PackageStatus, PackageText, PauseState, PurchaseTerms, Referral, ReferralConfig, ReferralPayout,
+    ReferralReward, ReferralStats, RefundPolicy, ResellerInfo, ResellerStats, Role, Session, SplitShare, TierPerk,
+    TrialGate, Voucher,
+};
+
+use crate::oracle::{Asset, PriceData, PriceOracleClient};
 
 use soroban_sdk::{
-    contract, contractimpl, panic_with_error, symbol_short, token::Client as TokenClient, Address,
-    Env, Symbol, Vec,
+    contract, contractimpl, panic_with_error, symbol_short, token::Client as TokenClient,
+    xdr::ToXdr, Address, Bytes, BytesN, Env, IntoVal, Map, String, Symbol, TryFromVal, Val, Vec,
 };
 
+/// Versão atual do layout de storage. Incrementar sempre que um `DataKey` ou
+/// struct persistida mudar de formato, adicionando o passo em `migrate`.
+pub const SCHEMA_VERSION: u32 = 2;
+
+/// Versão assumida quando `DataKey::SchemaVersion` não existe (deploy original).
+const LEGACY_SCHEMA_VERSION: u32 = 1;
+
+/// Tamanho máximo de página em `get_packages`.
+pub const MAX_PAGE_LIMIT: u32 = 50;
+
+/// Base das regras de divisão de receita (100% em basis points).
+pub const BPS_DENOMINATOR: u32 = 10_000;
+
+/// Máximo de destinatários em uma regra de divisão.
+pub const MAX_SPLIT_SHARES: u32 = 10;
+
+/// Casas decimais de `Package.ref_price` (centavos da moeda de referência).
+pub const REF_PRICE_DECIMALS: u32 = 2;
+
+/// Limites dos textos de pacote, em bytes UTF-8.
+pub const MAX_NAME_LEN: u32 = 32;
+pub const MAX_SPEED_MESSAGE_LEN: u32 = 64;
+pub const MAX_DESCRIPTION_LEN: u32 = 280;
+
+/// Máximo de vouchers por chamada de `issue_vouchers`.
+pub const MAX_VOUCHER_BATCH: u32 = 100;
+
+/// Tamanho mínimo (bytes) do código de um voucher. Os hashes ficam públicos
+/// on-chain, então códigos curtos seriam quebrados por força bruta offline.
+pub const MIN_VOUCHER_CODE_LEN: u32 = 16;
+
+/// Maior id sondado ao procurar pacotes fora do `PackageIds` na migração.
+const LEGACY_PACKAGE_SCAN_MAX: u32 = 64;
+
 
 #[contract]
 pub struct ConectaBrasil;
@@ -12,7 +54,7 @@ pub struct ConectaBrasil;
     // -------------------------------------------------------------
     // HELPERS (tempo)
     // -------------------------------------------------------------
-    fn remaining_at(env: &Env, s: &Session, now: u64) -> u64 {
+    fn remaining_at(_env: &Env, s: &Session, now: u64) -> u64 {
         if s.started_at == 0 {
             s.remaining_secs
         } else {
@@ -37,353 +79,2627 @@ pub struct ConectaBrasil;
             .set(&DataKey::Session(owner.clone()), s);
     }
 
-    // -------------------------------------------------------------
-    // TIPOS AUXILIARES (somente neste arquivo)
-    // -------------------------------------------------------------
+    // helper p/ admin (instance): falha com NotInitialized se init não rodou
+    fn load_admin(env: &Env) -> Address {
+        env.storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .unwrap_or_else(|| panic_with_error!(env, Error::NotInitialized))
+    }
 
-    // storage helpers p/ OrderRec (persistent)
-    fn load_order(env: &Env, owner: &Address, order_id: u128) -> Option<OrderRec> {
+    // helper p/ tesouraria (instance): sem Treasury configurado, a receita vai ao admin
+    fn load_treasury(env: &Env) -> Address {
+        env.storage()
+            .instance()
+            .get(&DataKey::Treasury)
+            .unwrap_or_else(|| load_admin(env))
+    }
+
+    // -------------------- FUNÇÕES HELPER PARA TOKENS ---------------------------
+    fn load_default_token(env: &Env) -> Address {
+        env.storage()
+            .instance()
+            .get(&DataKey::Token)
+            .unwrap_or_else(|| panic_with_error!(env, Error::NotInitialized))
+    }
+
+    fn load_extra_tokens(env: &Env) -> Vec<Address> {
+        env.storage()
+            .instance()
+            .get(&DataKey::AcceptedTokens)
+            .unwrap_or(Vec::new(env))
+    }
+
+    fn require_accepted_token(env: &Env, token: &Address) {
+        if *token != load_default_token(env) && !load_extra_tokens(env).contains(token) {
+            panic_with_error!(env, Error::TokenNotAccepted);
+        }
+    }
+
+    // preço do pacote em `token`: `price` no token padrão, tabela nos demais
+    // (o token precisa estar na allowlist)
+    fn package_price(env: &Env, pkg: &Package, token: &Address) -> i128 {
+        if *token == load_default_token(env) {
+            return pkg.price;
+        }
+        require_accepted_token(env, token);
+        pkg.token_prices
+            .get(token.clone())
+            .unwrap_or_else(|| panic_with_error!(env, Error::NoPriceForToken))
+    }
+
+    // -------------------- FUNÇÕES HELPER PARA ORÁCULO ---------------------------
+    fn load_oracle_config(env: &Env) -> OracleConfig {
         env.storage()
+            .instance()
+            .get(&DataKey::Oracle)
+            .unwrap_or_else(|| panic_with_error!(env, Error::PriceUnavailable))
+    }
+
+    fn oracle_price(env: &Env, oracle: &PriceOracleClient, asset: Asset) -> PriceData {
+        match oracle.lastprice(&asset) {
+            Some(data) if data.price > 0 => data,
+            _ => panic_with_error!(env, Error::PriceUnavailable),
+        }
+    }
+
+    // converte `ref_price` (centavos da moeda de referência) em unidades de
+    // `token` pela taxa cruzada do oráculo, arredondando para cima. Devolve
+    // também o timestamp da cotação mais antiga usada. Cotações externas
+    // podem ser qualquer valor: estouro na conta vira PriceUnavailable.
+    fn convert_ref_price(env: &Env, ref_price: i128, token: &Address) -> (i128, u64) {
+        require_accepted_token(env, token);
+        let config = load_oracle_config(env);
+        let oracle = PriceOracleClient::new(env, &config.oracle);
+        let currency = oracle_price(env, &oracle, Asset::Other(config.currency));
+        let token_px = oracle_price(env, &oracle, Asset::Stellar(token.clone()));
+
+        let token_decimals = TokenClient::new(env, token).decimals();
+        let numerator = 10i128
+            .checked_pow(token_decimals)
+            .and_then(|unit| ref_price.checked_mul(unit))
+            .and_then(|value| value.checked_mul(currency.price));
+        let denominator = token_px.price.checked_mul(10i128.pow(REF_PRICE_DECIMALS));
+        let amount = numerator
+            .zip(denominator)
+            .and_then(|(n, d)| n.checked_add(d - 1).map(|n| n / d))
+            .unwrap_or_else(|| panic_with_error!(env, Error::PriceUnavailable));
+        (amount, currency.timestamp.min(token_px.timestamp))
+    }
+
+    // valor a cobrar em `token`: preço fixo ou conversão do preço de
+    // referência, conferida contra os limites do comprador
+    fn purchase_price(env: &Env, pkg: &Package, token: &Address, quote: &Option<OracleQuote>) -> i128 {
+        let Some(ref_price) = pkg.ref_price else {
+            return package_price(env, pkg, token);
+        };
+        let Some(quote) = quote else {
+            panic_with_error!(env, Error::QuoteRequired);
+        };
+        let (amount, quoted_at) = convert_ref_price(env, ref_price, token);
+        if env.ledger().timestamp().saturating_sub(quoted_at) > quote.max_staleness_secs {
+            panic_with_error!(env, Error::StalePrice);
+        }
+        let tolerance = quote.expected_amount * quote.max_slippage_bps as i128 / BPS_DENOMINATOR as i128;
+        if amount > quote.expected_amount + tolerance {
+            panic_with_error!(env, Error::SlippageExceeded);
+        }
+        amount
+    }
+
+    // -------------------- FUNÇÕES HELPER PARA COMPRA ----------------------------
+    // credita `secs` na sessão geral (compatibilidade) e na sessão da ordem;
+    // devolve o novo saldo da sessão geral
+    fn credit_time(env: &Env, owner: &Address, order_id: u128, secs: u32) -> u64 {
+        let mut s = load_session(env, owner);
+        s.remaining_secs = s.remaining_secs.saturating_add(secs as u64);
+        save_session(env, owner, &s);
+
+        let mut order_session = load_order_session(env, owner, order_id);
+        order_session.remaining_secs = order_session.remaining_secs.saturating_add(secs as u64);
+        save_order_session(env, owner, order_id, &order_session);
+        s.remaining_secs
+    }
+
+    // cria para `owner` uma ordem já creditada (voucher, bônus, pontos) e
+    // emite os eventos de status e grant; devolve o order_id
+    fn open_credited_order(env: &Env, owner: &Address, ord: &OrderRec) -> u128 {
+        let order_id = next_order_id(env, owner);
+        save_order(env, owner, order_id, ord);
+        add_user_order(env, owner, order_id);
+        let remaining = credit_time(env, owner, order_id, ord.duration_secs);
+        emit_order_status(env, owner, order_id, OrderStatus::Pending, OrderStatus::Credited);
+        emit_grant(env, owner, order_id, remaining);
+        order_id
+    }
+
+    // ajustes que o contrato aplica a uma compra
+    struct PurchaseOptions {
+        quote: Option<OracleQuote>, // limites do comprador p/ preço via oráculo
+        credit: bool,      // ordem já nasce creditada (sem passar por grant)
+        discount_bps: u32, // desconto de faixa (atacado ou fidelidade) sobre o preço de tabela
+        bonus_bps: u32,    // segundos extras sobre a duração do pacote
+    }
+
+    // resultado de `purchase`
+    struct PurchaseReceipt {
+        order_id: u128,
+        price: i128,         // valor efetivamente cobrado
+        tier_discount: i128, // parte do desconto vinda de `discount_bps`
+    }
+
+    // núcleo das compras: `payer` paga e a ordem pertence a `owner`. Sem
+    // `credit`, a ordem fica Pending (e retida em escrow se o modo estiver
+    // ligado); a indicação só rende quando a ordem é creditada. O cupom
+    // dos termos (do pagador) é aplicado depois do desconto de faixa.
+    fn purchase(
+        env: &Env,
+        payer: &Address,
+        owner: &Address,
+        package_id: u32,
+        token: &Address,
+        terms: &PurchaseTerms,
+        opts: PurchaseOptions,
+    ) -> PurchaseReceipt {
+        if !env.storage().instance().has(&DataKey::Admin) {
+            panic_with_error!(env, Error::NotInitialized);
+        }
+
+        let pkg: Package = env
+            .storage()
             .persistent()
-            .get::<_, OrderRec>(&DataKey::Order(owner.clone(), order_id))
+            .get(&DataKey::Package(package_id))
+            .unwrap_or_else(|| panic_with_error!(env, Error::PackageNotFound));
+        if pkg.status != PackageStatus::Active {
+            panic_with_error!(env, Error::PackageInactive);
+        }
+        if pkg.is_trial {
+            panic_with_error!(env, Error::TrialPackageMismatch);
+        }
+        let list_price = purchase_price(env, &pkg, token, &opts.quote);
+        let tier_discount = bps_of(list_price, opts.discount_bps);
+        let mut price = list_price - tier_discount;
+        let coupon_discount = match &terms.coupon {
+            Some(code) => apply_coupon(env, code, payer, package_id, token, price),
+            None => 0,
+        };
+        price -= coupon_discount;
+        let bonus_secs = pkg.duration_secs as u64 * opts.bonus_bps as u64 / BPS_DENOMINATOR as u64;
+        let duration_secs = pkg.duration_secs.saturating_add(bonus_secs as u32);
+        check_buyer_terms(env, price, &pkg, terms);
+
+        let status = if opts.credit {
+            OrderStatus::Credited
+        } else {
+            OrderStatus::Pending
+        };
+        let ord = OrderRec {
+            package_id,
+            status,
+            price_paid: price,
+            token: token.clone(),
+            duration_secs,
+            purchased_at: env.ledger().timestamp(),
+            limits: pkg.limits.clone(),
+            payer: payer.clone(),
+        };
+        let token_client = TokenClient::new(env, token);
+        let escrowed = !opts.credit && is_escrow_enabled(env);
+        if escrowed {
+            // modo escrow: o contrato segura o pagamento até o grant
+            token_client.transfer(payer, &env.current_contract_address(), &price);
+        } else {
+            pay_out(env, &token_client, payer, package_id, price); // se der erro do SAC, diagnostics mostram
+        }
+
+        // order_id determinístico pelo contador do dono
+        let order_id: u128 = next_order_id(env, owner);
+        if escrowed {
+            hold_escrow(env, owner, order_id, price);
+        }
+        save_order(env, owner, order_id, &ord);
+        add_user_order(env, owner, order_id);
+
+        if let Some(code) = &terms.coupon {
+            env.events().publish(
+                (symbol_short!("coupon"), code.clone(), payer.clone()),
+                (owner.clone(), order_id, coupon_discount, token.clone()),
+            );
+        }
+        let stage = if opts.credit { "completed" } else { "created" };
+        env.events().publish(
+            (Symbol::new(env, "purchase"), Symbol::new(env, stage)),
+            (owner.clone(), package_id, order_id, price, token.clone(), payer.clone()),
+        );
+        if opts.credit {
+            let remaining = credit_time(env, owner, order_id, duration_secs);
+            emit_order_status(env, owner, order_id, OrderStatus::Pending, OrderStatus::Credited);
+            emit_grant(env, owner, order_id, remaining);
+            reward_referral(env, owner, order_id, &ord);
+        }
+        PurchaseReceipt {
+            order_id,
+            price,
+            tier_discount,
+        }
     }
-    fn save_order(env: &Env, owner: &Address, order_id: u128, rec: &OrderRec) {
+
+    // `bps` basis points de `amount`, arredondado para baixo
+    fn bps_of(amount: i128, bps: u32) -> i128 {
+        amount * bps as i128 / BPS_DENOMINATOR as i128
+    }
+
+    // -------------------- FUNÇÕES HELPER PARA VOUCHERS --------------------------
+    fn load_voucher(env: &Env, hash: &BytesN<32>) -> Option<Voucher> {
+        env.storage().persistent().get(&DataKey::Voucher(hash.clone()))
+    }
+
+    fn save_voucher(env: &Env, hash: &BytesN<32>, voucher: &Voucher) {
         env.storage()
             .persistent()
-            .set(&DataKey::Order(owner.clone(), order_id), rec);
+            .set(&DataKey::Voucher(hash.clone()), voucher);
     }
-    
-    fn get_user_orders_list(env: &Env, owner: &Address) -> Vec<u128> {
-    env.storage()
-        .persistent()
-        .get(&DataKey::UserOrders(owner.clone()))
-        .unwrap_or(Vec::new(env))
+
+    // -------------------- FUNÇÕES HELPER PARA CUPONS ----------------------------
+    fn load_coupon(env: &Env, code: &Symbol) -> Coupon {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Coupon(code.clone()))
+            .unwrap_or_else(|| panic_with_error!(env, Error::CouponNotFound))
     }
 
-    // helper para gerenciar lista de ordens do usuário
-    fn add_user_order(env: &Env, owner: &Address, order_id: u128) {
-        let key = DataKey::UserOrders(owner.clone());
-        let mut orders: Vec<u128> = env.storage().persistent().get(&key).unwrap_or(Vec::new(env));
-        orders.push_back(order_id);
-        env.storage().persistent().set(&key, &orders);
+    fn load_coupon_uses(env: &Env, code: &Symbol, payer: &Address) -> u32 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::CouponUses(code.clone(), payer.clone()))
+            .unwrap_or(0)
     }
 
-    // helper p/ contador determinístico (instance): NextOrder(owner) -> u128
-    fn next_order_id(env: &Env, owner: &Address) -> u128 {
-        let key = DataKey::NextOrder(owner.clone());
-        let current: u128 = env.storage().instance().get(&key).unwrap_or(0);
-        let next = current + 1;
-        env.storage().instance().set(&key, &next);
-        next
+    // valida o cupom para esta compra, registra o uso e devolve o desconto
+    // sobre `price` (nunca maior que o próprio preço)
+    fn apply_coupon(env: &Env, code: &Symbol, payer: &Address, package_id: u32, token: &Address, price: i128) -> i128 {
+        let mut coupon = load_coupon(env, code);
+        let rules = &coupon.rules;
+        let now = env.ledger().timestamp();
+        if !coupon.active || now < rules.starts_at || (rules.ends_at != 0 && now > rules.ends_at) {
+            panic_with_error!(env, Error::CouponNotValid);
+        }
+        if !rules.package_ids.is_empty() && !rules.package_ids.contains(package_id) {
+            panic_with_error!(env, Error::CouponNotApplicable);
+        }
+        let discount = match &rules.discount {
+            CouponDiscount::Percent(bps) => bps_of(price, *bps),
+            CouponDiscount::Fixed(coupon_token, amount) => {
+                if coupon_token != token {
+                    panic_with_error!(env, Error::CouponNotApplicable);
+                }
+                (*amount).min(price)
+            }
+        };
+
+        let user_uses = load_coupon_uses(env, code, payer);
+        if (rules.max_uses != 0 && coupon.uses >= rules.max_uses)
+            || (rules.max_uses_per_user != 0 && user_uses >= rules.max_uses_per_user)
+        {
+            panic_with_error!(env, Error::CouponExhausted);
+        }
+        coupon.uses += 1;
+        env.storage()
+            .persistent()
+            .set(&DataKey::Coupon(code.clone()), &coupon);
+        env.storage()
+            .persistent()
+            .set(&DataKey::CouponUses(code.clone(), payer.clone()), &(user_uses + 1));
+        discount
     }
 
-    // -------------------- FUNÇÕES HELPER PARA ORDER SESSION --------------------
-    fn load_order_session(env: &Env, owner: &Address, order_id: u128) -> OrderSession {
+    // -------------------- FUNÇÕES HELPER PARA INDICAÇÕES -------------------------
+    fn load_referral(env: &Env, referee: &Address) -> Option<Referral> {
         env.storage()
             .persistent()
-            .get(&DataKey::OrderSession(owner.clone(), order_id))
-            .unwrap_or(OrderSession {
-                order_id,
-                remaining_secs: 0,
-                started_at: 0,
+            .get(&DataKey::Referral(referee.clone()))
+    }
+
+    fn load_referral_stats(env: &Env, referrer: &Address) -> ReferralStats {
+        env.storage()
+            .persistent()
+            .get(&DataKey::ReferralStats(referrer.clone()))
+            .unwrap_or(ReferralStats {
+                referees: 0,
+                rewarded_purchases: 0,
+                bonus_secs: 0,
+                cashback: Map::new(env),
             })
     }
 
-    fn save_order_session(env: &Env, owner: &Address, order_id: u128, session: &OrderSession) {
+    fn save_referral_stats(env: &Env, referrer: &Address, stats: &ReferralStats) {
         env.storage()
             .persistent()
-            .set(&DataKey::OrderSession(owner.clone(), order_id), session);
+            .set(&DataKey::ReferralStats(referrer.clone()), stats);
     }
 
-    fn remaining_at_order(env: &Env, session: &OrderSession, now: u64) -> u64 {
-        if session.started_at == 0 {
-            session.remaining_secs
+    fn get_cashback_due(env: &Env, referrer: &Address, token: &Address) -> i128 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::CashbackDue(referrer.clone(), token.clone()))
+            .unwrap_or(0)
+    }
+
+    fn add_cashback_due(env: &Env, referrer: &Address, token: &Address, delta: i128) {
+        let key = DataKey::CashbackDue(referrer.clone(), token.clone());
+        let due = get_cashback_due(env, referrer, token) + delta;
+        if due == 0 {
+            env.storage().persistent().remove(&key);
         } else {
-            let elapsed = now.saturating_sub(session.started_at);
-            session.remaining_secs.saturating_sub(elapsed)
+            env.storage().persistent().set(&key, &due);
         }
     }
 
+    // recompensa o indicador de `owner` se a ordem (recém-creditada)
+    // qualificar. Bônus em segundos vira uma ordem creditada (preço 0) do
+    // indicador; cashback fica devido ao indicador e sai da tesouraria em
+    // `pay_cashback`, já que a receita inteira foi para ela. A recompensa fica
+    // registrada na ordem para ser estornada no reembolso.
+    fn reward_referral(env: &Env, owner: &Address, order_id: u128, ord: &OrderRec) {
+        let Some(config) = env
+            .storage()
+            .instance()
+            .get::<_, ReferralConfig>(&DataKey::ReferralConfig)
+        else {
+            return;
+        };
+        let Some(mut referral) = load_referral(env, owner) else {
+            return;
+        };
+        // quem paga não pode receber a própria recompensa (ex.: buy_for)
+        if referral.referrer == ord.payer
+            || ord.price_paid < config.min_price
+            || (config.max_rewards_per_referee != 0 && referral.rewards >= config.max_rewards_per_referee)
+        {
+            return;
+        }
 
-    // -------------------------------------------------------------
-    // EVENTOS
-    // -------------------------------------------------------------
+        let referrer = referral.referrer.clone();
+        let mut stats = load_referral_stats(env, &referrer);
+        let mut payout = ReferralPayout {
+            referrer: referrer.clone(),
+            bonus_order: None,
+            bonus_secs: 0,
+            cashback: 0,
+        };
+        match config.reward {
+            ReferralReward::BonusSecs(secs) => {
+                let bonus_order = open_credited_order(
+                    env,
+                    &referrer,
+                    &OrderRec {
+                        package_id: ord.package_id,
+                        status: OrderStatus::Credited,
+                        price_paid: 0,
+                        token: ord.token.clone(),
+                        duration_secs: secs,
+                        purchased_at: env.ledger().timestamp(),
+                        limits: ord.limits.clone(),
+                        payer: referrer.clone(),
+                    },
+                );
+                stats.bonus_secs = stats.bonus_secs.saturating_add(secs as u64);
+                payout.bonus_order = Some(bonus_order);
+                payout.bonus_secs = secs;
+            }
+            ReferralReward::CashbackBps(bps) => {
+                payout.cashback = bps_of(ord.price_paid, bps);
+                add_cashback_due(env, &referrer, &ord.token, payout.cashback);
+                let total = stats.cashback.get(ord.token.clone()).unwrap_or(0);
+                stats.cashback.set(ord.token.clone(), total + payout.cashback);
+            }
+        }
+        stats.rewarded_purchases = stats.rewarded_purchases.saturating_add(1);
+        save_referral_stats(env, &referrer, &stats);
+        referral.rewards += 1;
+        env.storage()
+            .persistent()
+            .set(&DataKey::Referral(owner.clone()), &referral);
+        env.storage()
+            .persistent()
+            .set(&DataKey::OrderReferral(owner.clone(), order_id), &payout);
 
-    fn emit_grant(env: &Env, owner: &Address, order_id: u128, new_remaining: u64) {
-        env.events()
-            .publish((symbol_short!("grant"), owner, order_id), new_remaining);
+        env.events().publish(
+            (Symbol::new(env, "referral_reward"), referrer, owner.clone()),
+            (payout.bonus_order, payout.cashback, ord.token.clone()),
+        );
     }
-    
-// -------------------------------------------------------------
-// CONTRATO
-// -------------------------------------------------------------
-#[contractimpl]
-impl ConectaBrasil {
-    
-    // -------------------- init / set_package (inalterados) --------------------
-    pub fn init(env: Env, admin: Address, token_asset: Address) {
-        if env.storage().instance().has(&DataKey::Admin) {
-            panic_with_error!(&env, Error::AlreadyInitialized);
+
+    // estorna a recompensa de indicação da ordem na proporção `refunded / paid`,
+    // como `forfeit_points`: o cashback devido pode ficar negativo (já pago) e
+    // os segundos bônus saem da ordem bônus e da sessão geral do indicador
+    fn forfeit_referral(env: &Env, owner: &Address, order_id: u128, token: &Address, refunded: i128, paid: i128) {
+        let key = DataKey::OrderReferral(owner.clone(), order_id);
+        let Some(payout) = env.storage().persistent().get::<_, ReferralPayout>(&key) else {
+            return;
+        };
+        env.storage().persistent().remove(&key);
+        let share = |amount: i128| if paid > 0 { amount * refunded.min(paid) / paid } else { amount };
+        let lost_cashback = share(payout.cashback);
+        let lost_secs = share(payout.bonus_secs as i128) as u64;
+        if lost_cashback <= 0 && lost_secs == 0 {
+            return;
+        }
+
+        let referrer = payout.referrer;
+        let mut stats = load_referral_stats(env, &referrer);
+        if lost_cashback > 0 {
+            add_cashback_due(env, &referrer, token, -lost_cashback);
+            let total = stats.cashback.get(token.clone()).unwrap_or(0);
+            stats.cashback.set(token.clone(), total - lost_cashback);
+        }
+        if let Some(bonus_order) = payout.bonus_order
+            && lost_secs > 0
+        {
+            let mut order_session = load_order_session(env, &referrer, bonus_order);
+            order_session.remaining_secs = order_session.remaining_secs.saturating_sub(lost_secs);
+            save_order_session(env, &referrer, bonus_order, &order_session);
+            let mut s = load_session(env, &referrer);
+            s.remaining_secs = s.remaining_secs.saturating_sub(lost_secs);
+            save_session(env, &referrer, &s);
+            stats.bonus_secs = stats.bonus_secs.saturating_sub(lost_secs);
+        }
+        save_referral_stats(env, &referrer, &stats);
+
+        env.events().publish(
+            (Symbol::new(env, "referral_forfeit"), referrer, owner.clone()),
+            (order_id, lost_secs, lost_cashback, token.clone()),
+        );
+    }
+
+    // -------------------- FUNÇÕES HELPER PARA FIDELIDADE -------------------------
+    fn load_loyalty_config(env: &Env) -> Option<LoyaltyConfig> {
+        env.storage().instance().get(&DataKey::LoyaltyConfig)
+    }
+
+    fn load_loyalty(env: &Env, owner: &Address) -> LoyaltyAccount {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Loyalty(owner.clone()))
+            .unwrap_or(LoyaltyAccount {
+                points: 0,
+                lifetime: 0,
+            })
+    }
+
+    fn save_loyalty(env: &Env, owner: &Address, account: &LoyaltyAccount) {
+        env.storage()
+            .persistent()
+            .set(&DataKey::Loyalty(owner.clone()), account);
+    }
+
+    // faixa correspondente a `lifetime` pontos (min_points 0 = faixa desligada)
+    fn tier_for(config: &LoyaltyConfig, lifetime: i128) -> LoyaltyTier {
+        let reached = |perk: &TierPerk| perk.min_points > 0 && lifetime >= perk.min_points;
+        if reached(&config.gold) {
+            LoyaltyTier::Gold
+        } else if reached(&config.silver) {
+            LoyaltyTier::Silver
+        } else if reached(&config.bronze) {
+            LoyaltyTier::Bronze
+        } else {
+            LoyaltyTier::None
+        }
+    }
+
+    // benefícios da faixa atual de `owner` (None = sem faixa ou sem programa)
+    fn loyalty_perk(env: &Env, owner: &Address) -> Option<TierPerk> {
+        let config = load_loyalty_config(env)?;
+        match tier_for(&config, load_loyalty(env, owner).lifetime) {
+            LoyaltyTier::Gold => Some(config.gold),
+            LoyaltyTier::Silver => Some(config.silver),
+            LoyaltyTier::Bronze => Some(config.bronze),
+            LoyaltyTier::None => None,
+        }
+    }
+
+    // credita pontos proporcionais ao valor pago em `token`
+    fn accrue_points(env: &Env, owner: &Address, order_id: u128, token: &Address, price: i128) {
+        let Some(config) = load_loyalty_config(env) else {
+            return;
+        };
+        let earned = bps_of(price, config.accrual_bps.get(token.clone()).unwrap_or(0));
+        if earned <= 0 {
+            return;
+        }
+        let mut account = load_loyalty(env, owner);
+        account.points += earned;
+        account.lifetime += earned;
+        save_loyalty(env, owner, &account);
+        env.storage()
+            .persistent()
+            .set(&DataKey::OrderPoints(owner.clone(), order_id), &earned);
+        env.events().publish(
+            (symbol_short!("points"), owner.clone()),
+            (order_id, earned, account.points, tier_for(&config, account.lifetime)),
+        );
+    }
+
+    // estorna os pontos da ordem na proporção `refunded / paid` do valor
+    // devolvido; pontos já resgatados deixam o saldo negativo, então comprar,
+    // resgatar e pedir reembolso não rende nada
+    fn forfeit_points(env: &Env, owner: &Address, order_id: u128, refunded: i128, paid: i128) {
+        let key = DataKey::OrderPoints(owner.clone(), order_id);
+        let Some(earned) = env.storage().persistent().get::<_, i128>(&key) else {
+            return;
+        };
+        env.storage().persistent().remove(&key);
+        let lost = if paid > 0 { earned * refunded.min(paid) / paid } else { earned };
+        if lost <= 0 {
+            return;
+        }
+        let mut account = load_loyalty(env, owner);
+        account.points -= lost;
+        account.lifetime -= lost;
+        save_loyalty(env, owner, &account);
+        let tier = load_loyalty_config(env)
+            .map_or(LoyaltyTier::None, |config| tier_for(&config, account.lifetime));
+        env.events().publish(
+            (symbol_short!("points"), owner.clone()),
+            (order_id, -lost, account.points, tier),
+        );
+    }
+
+    // -------------------- FUNÇÕES HELPER PARA REVENDEDORES -----------------------
+    fn load_reseller(env: &Env, reseller: &Address) -> Option<ResellerInfo> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Reseller(reseller.clone()))
+    }
+
+    fn load_tier_discount(env: &Env, tier: u32) -> Option<u32> {
+        env.storage().instance().get(&DataKey::ResellerTier(tier))
+    }
+
+    // desconto de atacado do revendedor cadastrado (0 se não cadastrado)
+    fn reseller_discount_bps(env: &Env, reseller: &Address) -> u32 {
+        load_reseller(env, reseller)
+            .and_then(|info| load_tier_discount(env, info.tier))
+            .unwrap_or(0)
+    }
+
+    fn load_reseller_stats(env: &Env, reseller: &Address, token: &Address) -> ResellerStats {
+        env.storage()
+            .persistent()
+            .get(&DataKey::ResellerStats(reseller.clone(), token.clone()))
+            .unwrap_or(ResellerStats {
+                orders: 0,
+                retail_value: 0,
+                paid: 0,
+                commission: 0,
+            })
+    }
+
+    // acumula `units` vendas: `paid` pago pelo revendedor, `commission` abatida
+    fn record_reseller_sale(env: &Env, reseller: &Address, token: &Address, units: u32, paid: i128, commission: i128) {
+        let mut stats = load_reseller_stats(env, reseller, token);
+        stats.orders = stats.orders.saturating_add(units);
+        stats.retail_value += paid + commission;
+        stats.paid += paid;
+        stats.commission += commission;
+        env.storage()
+            .persistent()
+            .set(&DataKey::ResellerStats(reseller.clone(), token.clone()), &stats);
+    }
+
+    // aborta se os termos atuais diferem do que o comprador viu na interface
+    fn check_buyer_terms(env: &Env, price: i128, pkg: &Package, terms: &PurchaseTerms) {
+        if terms.max_price.is_some_and(|max| price > max) {
+            panic_with_error!(env, Error::PriceAboveMax);
+        }
+        if terms.expected_duration_secs.is_some_and(|secs| secs != pkg.duration_secs) {
+            panic_with_error!(env, Error::DurationMismatch);
+        }
+    }
+
+    // -------------------- FUNÇÕES HELPER PARA DIVISÃO DE RECEITA ---------------
+    // regra global em instance; por pacote em persistent, como o catálogo
+    fn load_split(env: &Env, package_id: Option<u32>) -> Vec<SplitShare> {
+        let shares = match package_id {
+            Some(id) => env.storage().persistent().get(&DataKey::PackageSplit(id)),
+            None => env.storage().instance().get(&DataKey::Split),
+        };
+        shares.unwrap_or(Vec::new(env))
+    }
+
+    fn validate_split(env: &Env, shares: &Vec<SplitShare>) {
+        if shares.is_empty() || shares.len() > MAX_SPLIT_SHARES {
+            panic_with_error!(env, Error::InvalidSplit);
+        }
+        let mut total: u32 = 0;
+        for share in shares.iter() {
+            if share.bps == 0 {
+                panic_with_error!(env, Error::InvalidSplit);
+            }
+            total = total.saturating_add(share.bps);
+        }
+        if total != BPS_DENOMINATOR {
+            panic_with_error!(env, Error::InvalidSplit);
+        }
+    }
+
+    // paga `amount` de `from` conforme a regra do pacote (ou global); sem
+    // regra, 100% vai para a tesouraria. Cada parte é arredondada para baixo
+    // e o resto do arredondamento vai para o primeiro destinatário da regra.
+    fn pay_out(env: &Env, token: &TokenClient, from: &Address, package_id: u32, amount: i128) {
+        let mut shares = load_split(env, Some(package_id));
+        if shares.is_empty() {
+            shares = load_split(env, None);
+        }
+        if shares.is_empty() {
+            token.transfer(from, &load_treasury(env), &amount);
+            return;
+        }
+
+        let mut parts: Vec<i128> = Vec::new(env);
+        let mut distributed: i128 = 0;
+        for share in shares.iter() {
+            let part = amount * share.bps as i128 / BPS_DENOMINATOR as i128;
+            distributed += part;
+            parts.push_back(part);
+        }
+        parts.set(0, parts.get_unchecked(0) + (amount - distributed));
+
+        for (share, part) in shares.iter().zip(parts.iter()) {
+            if part > 0 {
+                token.transfer(from, &share.recipient, &part);
+                env.events()
+                    .publish((symbol_short!("split"), share.recipient), (package_id, part));
+            }
+        }
+    }
+
+    // -------------------- FUNÇÕES HELPER PARA ESCROW ----------------------------
+    fn is_escrow_enabled(env: &Env) -> bool {
+        env.storage()
+            .instance()
+            .get(&DataKey::EscrowMode)
+            .unwrap_or(false)
+    }
+
+    fn load_escrow(env: &Env, owner: &Address, order_id: u128) -> i128 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Escrow(owner.clone(), order_id))
+            .unwrap_or(0)
+    }
+
+    fn hold_escrow(env: &Env, owner: &Address, order_id: u128, amount: i128) {
+        env.storage()
+            .persistent()
+            .set(&DataKey::Escrow(owner.clone(), order_id), &amount);
+        env.events().publish(
+            (Symbol::new(env, "escrow_held"), owner.clone()),
+            (order_id, amount),
+        );
+    }
+
+    // repassa o valor retido de uma ordem para a tesouraria/divisão (no-op sem escrow)
+    fn release_escrow(env: &Env, owner: &Address, order_id: u128, ord: &OrderRec) {
+        let amount = load_escrow(env, owner, order_id);
+        if amount == 0 {
+            return;
+        }
+        env.storage()
+            .persistent()
+            .remove(&DataKey::Escrow(owner.clone(), order_id));
+        let token = TokenClient::new(env, &ord.token);
+        pay_out(env, &token, &env.current_contract_address(), ord.package_id, amount);
+        env.events().publish(
+            (Symbol::new(env, "escrow_released"), owner.clone()),
+            (order_id, amount),
+        );
+    }
+
+    // -------------------- FUNÇÕES HELPER PARA ESTADO DA ORDEM -------------------
+    fn can_transition(from: OrderStatus, to: OrderStatus) -> bool {
+        use OrderStatus::*;
+        matches!(
+            (from, to),
+            (Pending, Credited | Refunded | Cancelled | Expired | Disputed)
+                | (Credited, Exhausted | Refunded | Disputed)
+                | (Disputed, Pending | Credited | Refunded | Cancelled)
+        )
+    }
+
+    // exige `expected`, com o erro mais específico para o estado atual
+    fn require_status(env: &Env, ord: &OrderRec, expected: OrderStatus) {
+        if ord.status == expected {
+            return;
+        }
+        let err = match (ord.status, expected) {
+            (OrderStatus::Refunded, _) => Error::OrderRefunded,
+            (OrderStatus::Credited | OrderStatus::Exhausted, OrderStatus::Pending) => {
+                Error::AlreadyGranted
+            }
+            (OrderStatus::Pending, OrderStatus::Credited) => Error::OrderNotCredited,
+            _ => Error::InvalidOrderTransition,
+        };
+        panic_with_error!(env, err);
+    }
+
+    fn emit_order_status(env: &Env, owner: &Address, order_id: u128, from: OrderStatus, to: OrderStatus) {
+        env.events().publish(
+            (Symbol::new(env, "order_status"), owner.clone()),
+            (order_id, from, to),
+        );
+    }
+
+    // única porta de mudança de estado: valida, grava e emite `order_status`
+    fn transition_order(env: &Env, owner: &Address, order_id: u128, ord: &mut OrderRec, to: OrderStatus) {
+        let from = ord.status;
+        if !can_transition(from, to) {
+            panic_with_error!(env, Error::InvalidOrderTransition);
+        }
+        ord.status = to;
+        save_order(env, owner, order_id, ord);
+        if from == OrderStatus::Disputed {
+            env.storage()
+                .persistent()
+                .remove(&DataKey::DisputedFrom(owner.clone(), order_id));
+        }
+        emit_order_status(env, owner, order_id, from, to);
+    }
+
+    fn load_disputed_from(env: &Env, owner: &Address, order_id: u128) -> Option<OrderStatus> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::DisputedFrom(owner.clone(), order_id))
+    }
+
+    // -------------------- FUNÇÕES HELPER PARA REEMBOLSO -------------------------
+    // como `require_status`, mas uma ordem em disputa vale pelo estado que
+    // tinha antes dela (reembolso é uma das saídas da disputa)
+    fn require_refund_status(env: &Env, owner: &Address, order_id: u128, ord: &OrderRec, expected: OrderStatus) {
+        if ord.status == OrderStatus::Disputed && load_disputed_from(env, owner, order_id) == Some(expected) {
+            return;
+        }
+        require_status(env, ord, expected);
+    }
+
+    // ordens migradas do schema v1 (`purchased_at` 0) têm `price_paid`
+    // estimado pelo preço do pacote na migração: o valor pago de fato é
+    // desconhecido, então não há reembolso on-chain para elas
+    fn require_known_price(env: &Env, ord: &OrderRec) {
+        if ord.purchased_at == 0 {
+            panic_with_error!(env, Error::NoRefundPolicy);
+        }
+    }
+
+    // ordem reembolsável: existe, tem valor pago conhecido e ainda está
+    // Pending (ou em disputa aberta a partir de Pending)
+    fn load_refundable_order(env: &Env, owner: &Address, order_id: u128) -> OrderRec {
+        let ord = load_order(env, owner, order_id)
+            .unwrap_or_else(|| panic_with_error!(env, Error::OrderNotFound));
+        require_refund_status(env, owner, order_id, &ord, OrderStatus::Pending);
+        require_known_price(env, &ord);
+        ord
+    }
+
+    // a receita foi para a tesouraria, então é ela quem paga reembolsos fora
+    // do escrow e precisa autorizá-los (já autorizado se for quem aprova)
+    fn refund_funder(env: &Env, approver: &Address) -> Address {
+        let treasury = load_treasury(env);
+        if treasury != *approver {
+            treasury.require_auth();
+        }
+        treasury
+    }
+
+    // devolve exatamente o valor pago a quem pagou a ordem: do escrow se
+    // houver, senão da tesouraria (ver `refund_funder`)
+    fn refund_order(env: &Env, owner: &Address, order_id: u128, ord: &mut OrderRec, approver: &Address) {
+        let token = TokenClient::new(env, &ord.token);
+        let held = load_escrow(env, owner, order_id);
+        if held > 0 {
+            env.storage()
+                .persistent()
+                .remove(&DataKey::Escrow(owner.clone(), order_id));
+            token.transfer(&env.current_contract_address(), &ord.payer, &held);
+        } else {
+            token.transfer(&refund_funder(env, approver), &ord.payer, &ord.price_paid);
+        }
+
+        forfeit_points(env, owner, order_id, ord.price_paid, ord.price_paid);
+        forfeit_referral(env, owner, order_id, &ord.token, ord.price_paid, ord.price_paid);
+        transition_order(env, owner, order_id, ord, OrderStatus::Refunded);
+        env.storage()
+            .persistent()
+            .remove(&DataKey::RefundRequest(owner.clone(), order_id));
+        env.events().publish(
+            (symbol_short!("refunded"), owner.clone()),
+            (order_id, ord.price_paid),
+        );
+    }
+
+    // valor proporcional aos segundos não usados, conforme a política do pacote
+    fn prorata_refund_amount(ord: &OrderRec, policy: &RefundPolicy, remaining: u64) -> i128 {
+        if ord.duration_secs == 0 {
+            return 0;
+        }
+        let unused = ord.price_paid * remaining as i128 / ord.duration_secs as i128;
+        let amount = unused * policy.percent_bps as i128 / BPS_DENOMINATOR as i128 - policy.fee;
+        amount.max(0)
+    }
+
+    // helper p/ papéis: admin possui implicitamente todos os papéis
+    fn has_role_or_admin(env: &Env, who: &Address, role: Role) -> bool {
+        if *who == load_admin(env) {
+            return true;
+        }
+        env.storage()
+            .persistent()
+            .get(&DataKey::Role(role, who.clone()))
+            .unwrap_or(false)
+    }
+
+    // exige que `caller` seja admin ou tenha `role`, e que tenha assinado
+    fn require_role(env: &Env, caller: &Address, role: Role) {
+        if !has_role_or_admin(env, caller, role) {
+            panic_with_error!(env, Error::Unauthorized);
+        }
+        caller.require_auth();
+    }
+
+    // -------------------- FUNÇÕES HELPER PARA CIRCUIT BREAKER ------------------
+    fn load_pause_state(env: &Env) -> PauseState {
+        env.storage()
+            .instance()
+            .get(&DataKey::PauseState)
+            .unwrap_or(PauseState {
+                purchases: false,
+                grants: false,
+                session_starts: false,
+            })
+    }
+
+    fn is_op_paused(state: &PauseState, op: Operation) -> bool {
+        match op {
+            Operation::Purchases => state.purchases,
+            Operation::Grants => state.grants,
+            Operation::SessionStarts => state.session_starts,
+        }
+    }
+
+    fn ensure_not_paused(env: &Env, op: Operation) {
+        if is_op_paused(&load_pause_state(env), op) {
+            panic_with_error!(env, Error::ContractPaused);
+        }
+    }
+
+    fn save_pause_state(env: &Env, caller: &Address, state: &PauseState) {
+        env.storage().instance().set(&DataKey::PauseState, state);
+        env.events()
+            .publish((symbol_short!("breaker"), caller.clone()), state.clone());
+    }
+
+    // -------------------- FUNÇÕES HELPER PARA CATÁLOGO -------------------------
+    fn load_package_ids(env: &Env) -> Vec<u32> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::PackageIds)
+            .unwrap_or(Vec::new(env))
+    }
+
+    fn load_package(env: &Env, id: u32) -> Package {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Package(id))
+            .unwrap_or_else(|| panic_with_error!(env, Error::PackageNotFound))
+    }
+
+    fn save_package_status(env: &Env, id: u32, pkg: &mut Package, status: PackageStatus) {
+        pkg.status = status;
+        env.storage().persistent().set(&DataKey::Package(id), pkg);
+        env.events()
+            .publish((Symbol::new(env, "pkg_status"), id), status);
+    }
+
+    fn no_limits() -> PackageLimits {
+        PackageLimits {
+            download_kbps: 0,
+            upload_kbps: 0,
+            data_cap_bytes: 0,
+            max_devices: 0,
+        }
+    }
+
+    fn validate_package_text(env: &Env, name: &String, speed_message: &String, description: &Option<String>) {
+        if name.len() > MAX_NAME_LEN {
+            panic_with_error!(env, Error::NameTooLong);
+        }
+        if speed_message.len() > MAX_SPEED_MESSAGE_LEN {
+            panic_with_error!(env, Error::SpeedMessageTooLong);
+        }
+        if description.as_ref().is_some_and(|d| d.len() > MAX_DESCRIPTION_LEN) {
+            panic_with_error!(env, Error::DescriptionTooLong);
+        }
+    }
+
+    fn remove_package_id(env: &Env, id: u32) {
+        let mut ids = load_package_ids(env);
+        if let Ok(pos) = ids.binary_search(id) {
+            ids.remove(pos);
+            env.storage().persistent().set(&DataKey::PackageIds, &ids);
+        }
+    }
+
+    // insere `id` mantendo o índice ordenado e sem duplicatas
+    fn add_package_id(env: &Env, id: u32) {
+        let mut ids = load_package_ids(env);
+        if let Err(pos) = ids.binary_search(id) {
+            ids.insert(pos, id);
+            env.storage().persistent().set(&DataKey::PackageIds, &ids);
+        }
+    }
+
+    // -------------------------------------------------------------
+    // TIPOS AUXILIARES (somente neste arquivo)
+    // -------------------------------------------------------------
+
+    // storage helpers p/ OrderRec (persistent)
+    fn load_order(env: &Env, owner: &Address, order_id: u128) -> Option<OrderRec> {
+        env.storage()
+            .persistent()
+            .get::<_, OrderRec>(&DataKey::Order(owner.clone(), order_id))
+    }
+    fn save_order(env: &Env, owner: &Address, order_id: u128, rec: &OrderRec) {
+        env.storage()
+            .persistent()
+            .set(&DataKey::Order(owner.clone(), order_id), rec);
+    }
+    
+    fn get_user_orders_list(env: &Env, owner: &Address) -> Vec<u128> {
+    env.storage()
+        .persistent()
+        .get(&DataKey::UserOrders(owner.clone()))
+        .unwrap_or(Vec::new(env))
+    }
+
+    // helper para gerenciar lista de ordens do usuário
+    fn add_user_order(env: &Env, owner: &Address, order_id: u128) {
+        let key = DataKey::UserOrders(owner.clone());
+        let mut orders: Vec<u128> = env.storage().persistent().get(&key).unwrap_or(Vec::new(env));
+        orders.push_back(order_id);
+        env.storage().persistent().set(&key, &orders);
+    }
+
+    // helper p/ contador determinístico (persistent): NextOrder(owner) -> u128
+    // se o dono ainda não foi migrado, continua do contador legado em instance
+    fn next_order_id(env: &Env, owner: &Address) -> u128 {
+        let key = DataKey::NextOrder(owner.clone());
+        let current: u128 = env
+            .storage()
+            .persistent()
+            .get(&key)
+            .unwrap_or_else(|| take_legacy_next_order(env, owner));
+        let next = current + 1;
+        env.storage().persistent().set(&key, &next);
+        next
+    }
+
+    // schema v1 guardava NextOrder(owner) em instance; remove e devolve o valor
+    fn take_legacy_next_order(env: &Env, owner: &Address) -> u128 {
+        let key = DataKey::NextOrder(owner.clone());
+        match env.storage().instance().get::<_, u128>(&key) {
+            Some(current) => {
+                env.storage().instance().remove(&key);
+                current
+            }
+            None => 0,
+        }
+    }
+
+    // -------------------- FUNÇÕES HELPER PARA MIGRAÇÃO -------------------------
+    fn load_schema_version(env: &Env) -> u32 {
+        env.storage()
+            .instance()
+            .get(&DataKey::SchemaVersion)
+            .unwrap_or(LEGACY_SCHEMA_VERSION)
+    }
+
+    // todos os pacotes ainda gravados em instance (layout v1, sem índice):
+    // sonda os ids até `LEGACY_PACKAGE_SCAN_MAX`; os demais vêm de
+    // `legacy_package_ids`
+    fn stored_package_ids(env: &Env, legacy_package_ids: &Vec<u32>) -> Vec<u32> {
+        let mut ids: Vec<u32> = Vec::new(env);
+        for id in (0..=LEGACY_PACKAGE_SCAN_MAX).chain(legacy_package_ids.iter()) {
+            if env.storage().instance().has(&DataKey::Package(id))
+                && let Err(pos) = ids.binary_search(id)
+            {
+                ids.insert(pos, id);
+            }
+        }
+        ids
+    }
+
+    // migra os dados globais do schema v1 para o atual: cada Package ganha
+    // os campos novos e sai de instance para persistent, com índice (PackageIds)
+    fn migrate_instance(env: &Env, legacy_package_ids: &Vec<u32>) {
+        for id in stored_package_ids(env, legacy_package_ids).iter() {
+            let key = DataKey::Package(id);
+            let mut raw: Map<Symbol, Val> = env.storage().instance().get(&key).unwrap();
+            // name/speed_message eram Symbol
+            for field in ["name", "speed_message"] {
+                let field = Symbol::new(env, field);
+                if let Ok(sym) = Symbol::try_from_val(env, &raw.get_unchecked(field.clone())) {
+                    raw.set(field, symbol_to_string(env, sym).into_val(env));
+                }
+            }
+            let no_token_prices: Map<Address, i128> = Map::new(env);
+            backfill_field(env, &mut raw, "status", PackageStatus::Active.into_val(env));
+            backfill_field(env, &mut raw, "description", ().into_val(env));
+            backfill_field(env, &mut raw, "limits", no_limits().into_val(env));
+            backfill_field(env, &mut raw, "token_prices", no_token_prices.into_val(env));
+            backfill_field(env, &mut raw, "ref_price", ().into_val(env));
+            backfill_field(env, &mut raw, "is_trial", false.into_val(env));
+            env.storage().instance().remove(&key);
+            env.storage().persistent().set(&key, &raw);
+            add_package_id(env, id);
+        }
+    }
+
+    // converte um Symbol (schema v1) em String: XDR de ScVal::Symbol é
+    // tipo (4 bytes) + tamanho (4 bytes) + bytes do símbolo (até 32)
+    fn symbol_to_string(env: &Env, sym: Symbol) -> String {
+        let xdr = sym.to_xdr(env);
+        let mut len_be = [0u8; 4];
+        xdr.slice(4..8).copy_into_slice(&mut len_be);
+        let len = u32::from_be_bytes(len_be);
+        let mut buf = [0u8; 32];
+        xdr.slice(8..8 + len).copy_into_slice(&mut buf[..len as usize]);
+        String::from_bytes(env, &buf[..len as usize])
+    }
+
+    // structs são gravadas como mapa campo -> valor; adiciona o campo novo se faltar
+    fn backfill_field(env: &Env, raw: &mut Map<Symbol, Val>, field: &str, value: Val) {
+        let field = Symbol::new(env, field);
+        if !raw.contains_key(field.clone()) {
+            raw.set(field, value);
+        }
+    }
+
+    // migra os registros de um dono para o layout atual; idempotente, pode ser
+    // chamado em lotes de donos quantas vezes for preciso
+    fn migrate_owner(env: &Env, owner: &Address) {
+        // NextOrder(owner) sai de instance e vai para persistent
+        let key = DataKey::NextOrder(owner.clone());
+        if !env.storage().persistent().has(&key) {
+            let current = take_legacy_next_order(env, owner);
+            if current > 0 {
+                env.storage().persistent().set(&key, &current);
+            }
+        }
+
+        for order_id in get_user_orders_list(env, owner).iter() {
+            let key = DataKey::Order(owner.clone(), order_id);
+            if let Some(mut raw) = env.storage().persistent().get::<_, Map<Symbol, Val>>(&key) {
+                migrate_order_rec(env, owner, &mut raw);
+                env.storage().persistent().set(&key, &raw);
+            }
+        }
+    }
+
+    // leva um OrderRec v1 ({package_id, credited}, mapa bruto) ao layout
+    // atual; registros já migrados não têm mais `credited` e ficam como estão
+    fn migrate_order_rec(env: &Env, owner: &Address, raw: &mut Map<Symbol, Val>) {
+        let credited_key = Symbol::new(env, "credited");
+        let Some(credited) = raw.get(credited_key.clone()) else {
+            return;
+        };
+        // snapshot dos termos: a ordem herda os termos atuais do pacote, e
+        // purchased_at 0 marca o valor pago como estimado (sem reembolso on-chain)
+        let package_id = u32::try_from_val(env, &raw.get_unchecked(Symbol::new(env, "package_id")))
+            .unwrap();
+        let token: Address = env.storage().instance().get(&DataKey::Token).unwrap();
+        let (price, duration_secs) = match env
+            .storage()
+            .persistent()
+            .get::<_, Package>(&DataKey::Package(package_id))
+        {
+            Some(pkg) => (pkg.price, pkg.duration_secs),
+            None => (0, 0),
+        };
+        let status = if bool::try_from_val(env, &credited).unwrap() {
+            OrderStatus::Credited
+        } else {
+            OrderStatus::Pending
+        };
+        raw.remove(credited_key);
+        raw.set(Symbol::new(env, "status"), status.into_val(env));
+        raw.set(Symbol::new(env, "price_paid"), price.into_val(env));
+        raw.set(Symbol::new(env, "token"), token.into_val(env));
+        raw.set(Symbol::new(env, "duration_secs"), duration_secs.into_val(env));
+        raw.set(Symbol::new(env, "purchased_at"), 0u64.into_val(env));
+        raw.set(Symbol::new(env, "limits"), no_limits().into_val(env));
+        // antes de buy_for, quem paga é sempre o dono
+        raw.set(Symbol::new(env, "payer"), owner.into_val(env));
+    }
+
+    // -------------------- FUNÇÕES HELPER PARA ORDER SESSION --------------------
+    fn load_order_session(env: &Env, owner: &Address, order_id: u128) -> OrderSession {
+        env.storage()
+            .persistent()
+            .get(&DataKey::OrderSession(owner.clone(), order_id))
+            .unwrap_or(OrderSession {
+                order_id,
+                remaining_secs: 0,
+                started_at: 0,
+            })
+    }
+
+    fn save_order_session(env: &Env, owner: &Address, order_id: u128, session: &OrderSession) {
+        env.storage()
+            .persistent()
+            .set(&DataKey::OrderSession(owner.clone(), order_id), session);
+    }
+
+    fn remaining_at_order(_env: &Env, session: &OrderSession, now: u64) -> u64 {
+        if session.started_at == 0 {
+            session.remaining_secs
+        } else {
+            let elapsed = now.saturating_sub(session.started_at);
+            session.remaining_secs.saturating_sub(elapsed)
+        }
+    }
+
+
+    // -------------------------------------------------------------
+    // EVENTOS
+    // -------------------------------------------------------------
+
+    fn emit_grant(env: &Env, owner: &Address, order_id: u128, new_remaining: u64) {
+        env.events()
+            .publish((symbol_short!("grant"), owner, order_id), new_remaining);
+    }
+    
+// -------------------------------------------------------------
+// CONTRATO
+// -------------------------------------------------------------
+#[contractimpl]
+impl ConectaBrasil {
+    
+    // -------------------- init / set_package (inalterados) --------------------
+    pub fn init(env: Env, admin: Address, token_asset: Address) {
+        if env.storage().instance().has(&DataKey::Admin) {
+            panic_with_error!(&env, Error::AlreadyInitialized);
+        }
+        env.storage().instance().set(&DataKey::Admin, &admin);
+        env.storage().instance().set(&DataKey::Token, &token_asset);
+        env.storage()
+            .instance()
+            .set(&DataKey::SchemaVersion, &SCHEMA_VERSION);
+        env.events()
+            .publish((symbol_short!("init"), admin.clone()), token_asset);
+    }
+
+    /// Cria/atualiza um pacote. `caller` deve ser admin ou ter `Role::CatalogManager`.
+    /// Pacotes novos nascem Active; ao atualizar, o status atual (e a flag de
+    /// teste grátis) é mantido.
+    /// Textos são validados contra `MAX_NAME_LEN` / `MAX_SPEED_MESSAGE_LEN` /
+    /// `MAX_DESCRIPTION_LEN`; traduções ficam em `set_package_text`.
+    pub fn set_package(env: Env, caller: Address, id: u32, price: i128, duration_secs: u32, text: PackageText, is_popular: bool) {
+        require_role(&env, &caller, Role::CatalogManager);
+        validate_package_text(&env, &text.name, &text.speed_message, &text.description);
+        let PackageText {
+            name,
+            speed_message,
+            description,
+        } = text;
+        let (status, limits, token_prices, ref_price, is_trial) = match env
+            .storage()
+            .persistent()
+            .get::<_, Package>(&DataKey::Package(id))
+        {
+            Some(existing) if existing.status == PackageStatus::Retired => {
+                panic_with_error!(&env, Error::PackageRetired)
+            }
+            Some(existing) => (
+                existing.status,
+                existing.limits,
+                existing.token_prices,
+                existing.ref_price,
+                existing.is_trial,
+            ),
+            None => (PackageStatus::Active, no_limits(), Map::new(&env), None, false),
+        };
+        // pacote de teste continua grátis (ver `set_package_trial`)
+        if is_trial && price != 0 {
+            panic_with_error!(&env, Error::TrialPackageMismatch);
+        }
+        let pkg = Package {
+            price,
+            duration_secs,
+            name,
+            speed_message,
+            description,
+            is_popular,
+            status,
+            limits,
+            token_prices,
+            ref_price,
+            is_trial,
+        };
+        env.storage().persistent().set(&DataKey::Package(id), &pkg);
+        add_package_id(&env, id);
+        env.events()
+            .publish((symbol_short!("pkg_set"), id), (price, duration_secs));
+    }
+
+    /// Define o preço de um pacote em um token aceito além do padrão
+    /// (admin ou `Role::CatalogManager`). O preço no token padrão é o `price`
+    /// de `set_package`.
+    pub fn set_package_price(env: Env, caller: Address, id: u32, token: Address, price: i128) {
+        require_role(&env, &caller, Role::CatalogManager);
+        if !load_extra_tokens(&env).contains(&token) {
+            panic_with_error!(&env, Error::TokenNotAccepted);
+        }
+        let mut pkg = load_package(&env, id);
+        if pkg.status == PackageStatus::Retired {
+            panic_with_error!(&env, Error::PackageRetired);
+        }
+        if pkg.is_trial {
+            panic_with_error!(&env, Error::TrialPackageMismatch);
+        }
+        pkg.token_prices.set(token.clone(), price);
+        env.storage().persistent().set(&DataKey::Package(id), &pkg);
+        env.events()
+            .publish((Symbol::new(&env, "pkg_price"), id), (token, price));
+    }
+
+    /// Define (ou remove, com `None`) o preço de um pacote na moeda de
+    /// referência do oráculo, em centavos (admin ou `Role::CatalogManager`).
+    /// Com preço de referência, as compras convertem o valor na hora via
+    /// oráculo e exigem um `OracleQuote` do comprador. Preço negativo é
+    /// recusado com `PriceUnavailable`.
+    pub fn set_package_ref_price(env: Env, caller: Address, id: u32, ref_price: Option<i128>) {
+        require_role(&env, &caller, Role::CatalogManager);
+        if ref_price.is_some_and(|price| price < 0) {
+            panic_with_error!(&env, Error::PriceUnavailable);
+        }
+        let mut pkg = load_package(&env, id);
+        if pkg.status == PackageStatus::Retired {
+            panic_with_error!(&env, Error::PackageRetired);
+        }
+        if pkg.is_trial && ref_price.is_some() {
+            panic_with_error!(&env, Error::TrialPackageMismatch);
+        }
+        pkg.ref_price = ref_price;
+        env.storage().persistent().set(&DataKey::Package(id), &pkg);
+        env.events()
+            .publish((Symbol::new(&env, "pkg_ref_price"), id), ref_price);
+    }
+
+    /// Valor atual do pacote em `token` (preço fixo ou convertido via oráculo),
+    /// para o comprador montar o `OracleQuote`. O token precisa ser aceito.
+    pub fn quote(env: Env, package_id: u32, token: Address) -> i128 {
+        let pkg = load_package(&env, package_id);
+        match pkg.ref_price {
+            Some(ref_price) => convert_ref_price(&env, ref_price, &token).0,
+            None => package_price(&env, &pkg, &token),
+        }
+    }
+
+    /// Remove o preço de um pacote em um token (admin ou `Role::CatalogManager`).
+    pub fn remove_package_price(env: Env, caller: Address, id: u32, token: Address) {
+        require_role(&env, &caller, Role::CatalogManager);
+        let mut pkg = load_package(&env, id);
+        pkg.token_prices.remove(token.clone());
+        env.storage().persistent().set(&DataKey::Package(id), &pkg);
+        env.events()
+            .publish((Symbol::new(&env, "pkg_price"), id), (token, ()));
+    }
+
+    /// Define velocidade, franquia e dispositivos de um pacote (admin ou
+    /// `Role::CatalogManager`). Vale para compras futuras; ordens já feitas
+    /// mantêm os limites comprados.
+    pub fn set_package_limits(env: Env, caller: Address, id: u32, limits: PackageLimits) {
+        require_role(&env, &caller, Role::CatalogManager);
+        let mut pkg = load_package(&env, id);
+        if pkg.status == PackageStatus::Retired {
+            panic_with_error!(&env, Error::PackageRetired);
+        }
+        pkg.limits = limits.clone();
+        env.storage().persistent().set(&DataKey::Package(id), &pkg);
+        env.events()
+            .publish((Symbol::new(&env, "pkg_limits"), id), limits);
+    }
+
+    /// Marca ou desmarca um pacote como teste grátis (admin ou
+    /// `Role::CatalogManager`). Pacotes de teste saem só via `claim_trial`,
+    /// sem cobrança, e não podem ser comprados; por isso precisam ter preço
+    /// zero, sem preço de referência nem preços em outros tokens.
+    pub fn set_package_trial(env: Env, caller: Address, id: u32, is_trial: bool) {
+        require_role(&env, &caller, Role::CatalogManager);
+        let mut pkg = load_package(&env, id);
+        if pkg.status == PackageStatus::Retired {
+            panic_with_error!(&env, Error::PackageRetired);
+        }
+        if is_trial && (pkg.price != 0 || pkg.ref_price.is_some() || !pkg.token_prices.is_empty()) {
+            panic_with_error!(&env, Error::TrialPackageMismatch);
+        }
+        pkg.is_trial = is_trial;
+        env.storage().persistent().set(&DataKey::Package(id), &pkg);
+        env.events()
+            .publish((Symbol::new(&env, "pkg_trial"), id), is_trial);
+    }
+
+    /// Define a tradução dos textos de um pacote (admin ou `Role::CatalogManager`).
+    pub fn set_package_text(env: Env, caller: Address, id: u32, locale: Locale, text: PackageText) {
+        require_role(&env, &caller, Role::CatalogManager);
+        load_package(&env, id);
+        validate_package_text(&env, &text.name, &text.speed_message, &text.description);
+        env.storage()
+            .persistent()
+            .set(&DataKey::PackageText(id, locale), &text);
+        env.events()
+            .publish((Symbol::new(&env, "pkg_text"), id), locale);
+    }
+
+    /// Remove a tradução de um pacote (admin ou `Role::CatalogManager`).
+    pub fn remove_package_text(env: Env, caller: Address, id: u32, locale: Locale) {
+        require_role(&env, &caller, Role::CatalogManager);
+        env.storage()
+            .persistent()
+            .remove(&DataKey::PackageText(id, locale));
+    }
+
+    /// Textos do pacote no idioma pedido; sem tradução, devolve os textos base.
+    pub fn get_package_text(env: Env, id: u32, locale: Locale) -> PackageText {
+        let pkg = load_package(&env, id);
+        env.storage()
+            .persistent()
+            .get(&DataKey::PackageText(id, locale))
+            .unwrap_or(PackageText {
+                name: pkg.name,
+                speed_message: pkg.speed_message,
+                description: pkg.description,
+            })
+    }
+
+    /// Tira um pacote de venda temporariamente (admin ou `Role::CatalogManager`).
+    pub fn deactivate_package(env: Env, caller: Address, id: u32) {
+        require_role(&env, &caller, Role::CatalogManager);
+        let mut pkg = load_package(&env, id);
+        if pkg.status == PackageStatus::Retired {
+            panic_with_error!(&env, Error::PackageRetired);
+        }
+        save_package_status(&env, id, &mut pkg, PackageStatus::Inactive);
+    }
+
+    /// Volta a vender um pacote Inactive (admin ou `Role::CatalogManager`).
+    pub fn reactivate_package(env: Env, caller: Address, id: u32) {
+        require_role(&env, &caller, Role::CatalogManager);
+        let mut pkg = load_package(&env, id);
+        if pkg.status == PackageStatus::Retired {
+            panic_with_error!(&env, Error::PackageRetired);
+        }
+        save_package_status(&env, id, &mut pkg, PackageStatus::Active);
+    }
+
+    /// Remove um pacote do catálogo de forma definitiva (admin ou `Role::CatalogManager`).
+    /// O registro continua em storage como Retired (histórico das ordens que
+    /// apontam para ele); o id sai das listagens.
+    pub fn remove_package(env: Env, caller: Address, id: u32) {
+        require_role(&env, &caller, Role::CatalogManager);
+        let mut pkg = load_package(&env, id);
+        save_package_status(&env, id, &mut pkg, PackageStatus::Retired);
+        remove_package_id(&env, id);
+    }
+
+    // Add this function to the contract implementation
+    pub fn get_package(env: Env, package_id: u32) -> Package {
+        load_package(&env, package_id)
+    }
+
+    // Retorna (order_id, package_id, status) de cada ordem do usuário
+    pub fn get_user_packages(env: Env, owner: Address) -> Vec<(u128, u32, OrderStatus)> {
+        let orders = get_user_orders_list(&env, &owner);
+        let mut packages = Vec::new(&env);
+        
+        for order_id in orders.iter() {
+            if let Some(order_rec) = load_order(&env, &owner, order_id) {
+                packages.push_back((order_id, order_rec.package_id, order_rec.status));
+            }
+        }
+        
+        packages
+    }
+
+    /// Retorna todos os pacotes cadastrados, em ordem crescente de id.
+    /// Sem limite de tamanho: para catálogos grandes use `get_packages`, a
+    /// única listagem com custo limitado.
+    pub fn get_all_packages(env: Env) -> Vec<(u32, Package)> {
+        let mut packages = Vec::new(&env);
+
+        for package_id in load_package_ids(&env).iter() {
+            if let Some(package) = env.storage().persistent().get(&DataKey::Package(package_id)) {
+                packages.push_back((package_id, package));
+            }
+        }
+
+        packages
+    }
+
+    /// Lista paginada do catálogo, em ordem crescente de id.
+    /// - `cursor`: último id da página anterior (None = começo do catálogo)
+    /// - `limit`: máximo de itens (limitado a `MAX_PAGE_LIMIT`)
+    pub fn get_packages(env: Env, cursor: Option<u32>, limit: u32) -> Vec<(u32, Package)> {
+        let ids = load_package_ids(&env);
+        let limit = limit.min(MAX_PAGE_LIMIT);
+        let start = match cursor {
+            None => 0,
+            Some(after) => match ids.binary_search(after) {
+                Ok(pos) => pos + 1,
+                Err(pos) => pos,
+            },
+        };
+
+        let mut packages = Vec::new(&env);
+        for i in start..ids.len() {
+            if packages.len() >= limit {
+                break;
+            }
+            let package_id = ids.get_unchecked(i);
+            if let Some(package) = env.storage().persistent().get(&DataKey::Package(package_id)) {
+                packages.push_back((package_id, package));
+            }
+        }
+
+        packages
+    }
+
+    /// Quantidade de pacotes no catálogo.
+    pub fn package_count(env: Env) -> u32 {
+        load_package_ids(&env).len()
+    }
+
+    // -------------------- troca de admin (duas etapas) -----------------------
+    /// Retorna o admin atual.
+    pub fn get_admin(env: Env) -> Address {
+        load_admin(&env)
+    }
+
+    /// Retorna o admin proposto que ainda não aceitou a troca (se houver).
+    pub fn get_pending_admin(env: Env) -> Option<Address> {
+        env.storage().instance().get(&DataKey::PendingAdmin)
+    }
+
+    /// Etapa 1: o admin atual propõe `new_admin`. Sobrescreve proposta anterior.
+    pub fn propose_admin(env: Env, new_admin: Address) {
+        let admin = load_admin(&env);
+        admin.require_auth();
+        env.storage().instance().set(&DataKey::PendingAdmin, &new_admin);
+        env.events().publish(
+            (Symbol::new(&env, "admin_proposed"), admin),
+            new_admin,
+        );
+    }
+
+    /// Etapa 2: o admin proposto aceita e passa a ser o admin.
+    pub fn accept_admin(env: Env, new_admin: Address) {
+        let pending: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::PendingAdmin)
+            .unwrap_or_else(|| panic_with_error!(&env, Error::NoPendingAdmin));
+        if pending != new_admin {
+            panic_with_error!(&env, Error::Unauthorized);
+        }
+        new_admin.require_auth();
+
+        let old_admin = load_admin(&env);
+        env.storage().instance().set(&DataKey::Admin, &new_admin);
+        env.storage().instance().remove(&DataKey::PendingAdmin);
+        env.events().publish(
+            (Symbol::new(&env, "admin_accepted"), old_admin),
+            new_admin,
+        );
+    }
+
+    /// Cancela uma troca de admin pendente (somente o admin atual).
+    pub fn cancel_admin_transfer(env: Env) {
+        let admin = load_admin(&env);
+        admin.require_auth();
+        let pending: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::PendingAdmin)
+            .unwrap_or_else(|| panic_with_error!(&env, Error::NoPendingAdmin));
+        env.storage().instance().remove(&DataKey::PendingAdmin);
+        env.events().publish(
+            (Symbol::new(&env, "admin_cancelled"), admin),
+            pending,
+        );
+    }
+
+    // -------------------- tesouraria ----------------------------------------
+    /// Define o endereço que recebe a receita das vendas (somente admin).
+    pub fn set_treasury(env: Env, treasury: Address) {
+        let admin = load_admin(&env);
+        admin.require_auth();
+        env.storage().instance().set(&DataKey::Treasury, &treasury);
+        env.events()
+            .publish((symbol_short!("treasury"), admin), treasury);
+    }
+
+    /// Endereço que recebe a receita (admin se nenhum foi configurado).
+    pub fn get_treasury(env: Env) -> Address {
+        load_treasury(&env)
+    }
+
+    /// Configura o oráculo SEP-40 e a moeda de referência (ex.: "BRL") usados
+    /// nos pacotes com `ref_price` (admin ou `Role::Treasurer`).
+    pub fn set_oracle(env: Env, caller: Address, oracle: Address, currency: Symbol) {
+        require_role(&env, &caller, Role::Treasurer);
+        let config = OracleConfig { oracle, currency };
+        env.storage().instance().set(&DataKey::Oracle, &config);
+        env.events()
+            .publish((symbol_short!("oracle"), caller), config);
+    }
+
+    pub fn get_oracle(env: Env) -> Option<OracleConfig> {
+        env.storage().instance().get(&DataKey::Oracle)
+    }
+
+    /// Aceita `token` como meio de pagamento além do padrão (admin ou `Role::Treasurer`).
+    pub fn add_token(env: Env, caller: Address, token: Address) {
+        require_role(&env, &caller, Role::Treasurer);
+        let mut tokens = load_extra_tokens(&env);
+        if token != load_default_token(&env) && !tokens.contains(&token) {
+            tokens.push_back(token.clone());
+            env.storage().instance().set(&DataKey::AcceptedTokens, &tokens);
+        }
+        env.events()
+            .publish((Symbol::new(&env, "token_added"), caller), token);
+    }
+
+    /// Deixa de aceitar `token` (admin ou `Role::Treasurer`). O token padrão
+    /// não pode ser removido; preços já cadastrados ficam sem efeito.
+    pub fn remove_token(env: Env, caller: Address, token: Address) {
+        require_role(&env, &caller, Role::Treasurer);
+        let mut tokens = load_extra_tokens(&env);
+        if let Some(pos) = tokens.first_index_of(&token) {
+            tokens.remove(pos);
+            env.storage().instance().set(&DataKey::AcceptedTokens, &tokens);
+        }
+        env.events()
+            .publish((Symbol::new(&env, "token_removed"), caller), token);
+    }
+
+    /// Tokens aceitos, começando pelo padrão.
+    pub fn get_accepted_tokens(env: Env) -> Vec<Address> {
+        let mut tokens = Vec::from_array(&env, [load_default_token(&env)]);
+        tokens.append(&load_extra_tokens(&env));
+        tokens
+    }
+
+    /// Define a divisão de receita de um pacote (`Some(id)`) ou global (`None`)
+    /// entre plataforma e ISPs parceiros. Somente admin: assim como
+    /// `set_treasury`, decide quem recebe a receita.
+    /// As partes devem somar 10000 bps.
+    pub fn set_split(env: Env, package_id: Option<u32>, shares: Vec<SplitShare>) {
+        let admin = load_admin(&env);
+        admin.require_auth();
+        validate_split(&env, &shares);
+        match package_id {
+            Some(id) => env.storage().persistent().set(&DataKey::PackageSplit(id), &shares),
+            None => env.storage().instance().set(&DataKey::Split, &shares),
+        }
+        env.events()
+            .publish((Symbol::new(&env, "split_set"), package_id), shares);
+    }
+
+    /// Remove a divisão de um pacote (volta à global) ou a global (volta a
+    /// 100% para a tesouraria). Somente admin.
+    pub fn clear_split(env: Env, package_id: Option<u32>) {
+        let admin = load_admin(&env);
+        admin.require_auth();
+        match package_id {
+            Some(id) => env.storage().persistent().remove(&DataKey::PackageSplit(id)),
+            None => env.storage().instance().remove(&DataKey::Split),
+        }
+        env.events()
+            .publish((Symbol::new(&env, "split_clear"), package_id), ());
+    }
+
+    /// Regra configurada para o pacote (`Some(id)`) ou global (`None`); vazia se não houver.
+    pub fn get_split(env: Env, package_id: Option<u32>) -> Vec<SplitShare> {
+        load_split(&env, package_id)
+    }
+
+    /// Liga/desliga o modo escrow (admin ou `Role::Treasurer`). Com escrow,
+    /// `buy_order` transfere o pagamento para o próprio contrato e o `grant`
+    /// repassa à tesouraria. Ordens já retidas seguem retidas se o modo for desligado.
+    pub fn set_escrow_mode(env: Env, caller: Address, enabled: bool) {
+        require_role(&env, &caller, Role::Treasurer);
+        env.storage().instance().set(&DataKey::EscrowMode, &enabled);
+        env.events()
+            .publish((Symbol::new(&env, "escrow_mode"), caller), enabled);
+    }
+
+    pub fn is_escrow_mode(env: Env) -> bool {
+        is_escrow_enabled(&env)
+    }
+
+    /// Valor retido no contrato para uma ordem (0 se não houver).
+    pub fn get_escrow(env: Env, owner: Address, order_id: u128) -> i128 {
+        load_escrow(&env, &owner, order_id)
+    }
+
+    // -------------------- papéis (RBAC) --------------------------------------
+    /// Concede `role` a `account` (somente admin).
+    pub fn grant_role(env: Env, role: Role, account: Address) {
+        let admin = load_admin(&env);
+        admin.require_auth();
+        env.storage()
+            .persistent()
+            .set(&DataKey::Role(role, account.clone()), &true);
+        env.events()
+            .publish((Symbol::new(&env, "role_granted"), account), role);
+    }
+
+    /// Revoga `role` de `account` (somente admin).
+    pub fn revoke_role(env: Env, role: Role, account: Address) {
+        let admin = load_admin(&env);
+        admin.require_auth();
+        env.storage()
+            .persistent()
+            .remove(&DataKey::Role(role, account.clone()));
+        env.events()
+            .publish((Symbol::new(&env, "role_revoked"), account), role);
+    }
+
+    /// Indica se `account` possui `role` (o admin possui todos).
+    pub fn has_role(env: Env, role: Role, account: Address) -> bool {
+        has_role_or_admin(&env, &account, role)
+    }
+
+    // -------------------- circuit breaker -----------------------------------
+    /// Suspende compras, grants e inícios de sessão (admin ou `Role::Pauser`).
+    /// `pause` / `pause_order` continuam liberados para ninguém queimar tempo.
+    pub fn pause_contract(env: Env, caller: Address) {
+        require_role(&env, &caller, Role::Pauser);
+        let state = PauseState {
+            purchases: true,
+            grants: true,
+            session_starts: true,
+        };
+        save_pause_state(&env, &caller, &state);
+    }
+
+    /// Libera todas as operações suspensas (admin ou `Role::Pauser`).
+    pub fn unpause_contract(env: Env, caller: Address) {
+        require_role(&env, &caller, Role::Pauser);
+        let state = PauseState {
+            purchases: false,
+            grants: false,
+            session_starts: false,
+        };
+        save_pause_state(&env, &caller, &state);
+    }
+
+    /// Suspende/libera uma operação específica (admin ou `Role::Pauser`).
+    pub fn set_paused(env: Env, caller: Address, op: Operation, paused: bool) {
+        require_role(&env, &caller, Role::Pauser);
+        let mut state = load_pause_state(&env);
+        match op {
+            Operation::Purchases => state.purchases = paused,
+            Operation::Grants => state.grants = paused,
+            Operation::SessionStarts => state.session_starts = paused,
+        }
+        save_pause_state(&env, &caller, &state);
+    }
+
+    pub fn get_pause_state(env: Env) -> PauseState {
+        load_pause_state(&env)
+    }
+
+    pub fn is_paused(env: Env, op: Operation) -> bool {
+        is_op_paused(&load_pause_state(&env), op)
+    }
+
+    // -------------------- upgrade / migração de schema ------------------------
+    /// Troca o WASM do contrato mantendo todo o storage (somente admin).
+    /// Depois do upgrade, chamar `migrate` para levar o storage ao novo layout.
+    pub fn upgrade(env: Env, new_wasm_hash: BytesN<32>) {
+        let admin = load_admin(&env);
+        admin.require_auth();
+        env.deployer()
+            .update_current_contract_wasm(new_wasm_hash.clone());
+        env.events()
+            .publish((symbol_short!("upgraded"), admin), new_wasm_hash);
+    }
+
+    /// Leva o storage do layout original (v1) até `SCHEMA_VERSION` (somente admin).
+    /// Dados por usuário não são enumeráveis on-chain, então os donos são
+    /// informados em lotes via `owners`; cada lote é idempotente.
+    /// Todo pacote gravado é migrado, inclusive os retirados do catálogo:
+    /// `legacy_package_ids` lista os que têm id acima de
+    /// `LEGACY_PACKAGE_SCAN_MAX` e estão fora do `PackageIds` (todo o catálogo
+    /// v1, que não tinha índice); só é lido na primeira chamada.
+    pub fn migrate(env: Env, owners: Vec<Address>, legacy_package_ids: Vec<u32>) -> u32 {
+        let admin = load_admin(&env);
+        admin.require_auth();
+
+        let from = load_schema_version(&env);
+        if from > SCHEMA_VERSION {
+            panic_with_error!(&env, Error::InvalidSchemaVersion);
+        }
+        if from < SCHEMA_VERSION {
+            migrate_instance(&env, &legacy_package_ids);
+            env.storage()
+                .instance()
+                .set(&DataKey::SchemaVersion, &SCHEMA_VERSION);
+            env.events()
+                .publish((symbol_short!("migrated"), from), SCHEMA_VERSION);
+        }
+
+        for owner in owners.iter() {
+            migrate_owner(&env, &owner);
+        }
+        SCHEMA_VERSION
+    }
+
+    /// Versão do layout de storage atualmente gravada.
+    pub fn schema_version(env: Env) -> u32 {
+        load_schema_version(&env)
+    }
+
+    // -------------------- NOVO: buy_order (compra sem crédito) ----------------
+
+    /// Compra um pacote criando uma ordem Pending (crédito via `grant`).
+    /// `terms.max_price` / `terms.expected_duration_secs` protegem o comprador:
+    /// a compra aborta se os termos atuais diferirem do que ele viu.
+    /// `terms.coupon` aplica um cupom promocional (ver `set_coupon`). A compra
+    /// recebe os benefícios da faixa do dono e rende pontos no `grant`.
+    pub fn buy_order(
+        env: Env,
+        owner: Address,
+        package_id: u32,
+        token: Address,
+        quote: Option<OracleQuote>,
+        terms: PurchaseTerms,
+    ) -> u128 {
+        owner.require_auth();
+        ensure_not_paused(&env, Operation::Purchases);
+
+        let perk = loyalty_perk(&env, &owner);
+        let receipt = purchase(
+            &env,
+            &owner,
+            &owner,
+            package_id,
+            &token,
+            &terms,
+            PurchaseOptions {
+                quote,
+                credit: false,
+                discount_bps: perk.as_ref().map_or(0, |p| p.discount_bps),
+                bonus_bps: perk.as_ref().map_or(0, |p| p.bonus_bps),
+            },
+        );
+        receipt.order_id
+    }
+
+    /// Compra e credita o pacote em uma única transação assinada pelo cliente
+    /// Unifica buy_order + grant para melhor UX (uma única assinatura)
+    /// Aceita as mesmas proteções de termos e o cupom de `buy_order`.
+    pub fn buy_and_grant(
+        env: Env,
+        owner: Address,
+        package_id: u32,
+        token: Address,
+        quote: Option<OracleQuote>,
+        terms: PurchaseTerms,
+    ) -> u128 {
+        owner.require_auth();
+        ensure_not_paused(&env, Operation::Purchases);
+        ensure_not_paused(&env, Operation::Grants);
+
+        let perk = loyalty_perk(&env, &owner);
+        let receipt = purchase(
+            &env,
+            &owner,
+            &owner,
+            package_id,
+            &token,
+            &terms,
+            PurchaseOptions {
+                quote,
+                credit: true,
+                discount_bps: perk.as_ref().map_or(0, |p| p.discount_bps),
+                bonus_bps: perk.as_ref().map_or(0, |p| p.bonus_bps),
+            },
+        );
+        let order_id = receipt.order_id;
+        accrue_points(&env, &owner, order_id, &token, receipt.price);
+        order_id
+    }
+
+    /// Presente: `payer` paga e a ordem (já creditada) pertence a `beneficiary`.
+    /// Só o pagador assina; ambos ficam registrados na ordem e nos eventos.
+    pub fn buy_for(
+        env: Env,
+        payer: Address,
+        beneficiary: Address,
+        package_id: u32,
+        token: Address,
+        quote: Option<OracleQuote>,
+        terms: PurchaseTerms,
+    ) -> u128 {
+        payer.require_auth();
+        ensure_not_paused(&env, Operation::Purchases);
+        ensure_not_paused(&env, Operation::Grants);
+
+        let order_id = purchase(
+            &env,
+            &payer,
+            &beneficiary,
+            package_id,
+            &token,
+            &terms,
+            PurchaseOptions {
+                quote,
+                credit: true,
+                discount_bps: 0,
+                bonus_bps: 0,
+            },
+        )
+        .order_id;
+        env.events().publish(
+            (symbol_short!("gift"), payer, beneficiary),
+            (package_id, order_id),
+        );
+        order_id
+    }
+
+    // -------------------- CUPONS DE DESCONTO ------------------------------------
+    /// Cria ou substitui o cupom `code` (somente admin). Substituir mantém a
+    /// contagem de usos. Em `rules`, `package_ids` vazio vale para todos os
+    /// pacotes; `ends_at`, `max_uses` e `max_uses_per_user` iguais a 0 = sem limite.
+    pub fn set_coupon(env: Env, code: Symbol, rules: CouponRules) {
+        let admin = load_admin(&env);
+        admin.require_auth();
+        let valid = match &rules.discount {
+            CouponDiscount::Percent(bps) => *bps <= BPS_DENOMINATOR,
+            CouponDiscount::Fixed(_, amount) => *amount >= 0,
+        };
+        if !valid {
+            panic_with_error!(&env, Error::InvalidDiscount);
+        }
+        let uses = env
+            .storage()
+            .persistent()
+            .get::<_, Coupon>(&DataKey::Coupon(code.clone()))
+            .map(|c| c.uses)
+            .unwrap_or(0);
+        let discount = rules.discount.clone();
+        env.storage().persistent().set(
+            &DataKey::Coupon(code.clone()),
+            &Coupon {
+                rules,
+                uses,
+                active: true,
+            },
+        );
+        env.events()
+            .publish((Symbol::new(&env, "coupon_set"), code), discount);
+    }
+
+    /// Desativa o cupom `code` (somente admin); `set_coupon` o reativa.
+    pub fn disable_coupon(env: Env, code: Symbol) {
+        let admin = load_admin(&env);
+        admin.require_auth();
+        let mut coupon = load_coupon(&env, &code);
+        coupon.active = false;
+        env.storage()
+            .persistent()
+            .set(&DataKey::Coupon(code.clone()), &coupon);
+        env.events()
+            .publish((Symbol::new(&env, "coupon_disabled"), code), ());
+    }
+
+    pub fn get_coupon(env: Env, code: Symbol) -> Option<Coupon> {
+        env.storage().persistent().get(&DataKey::Coupon(code))
+    }
+
+    /// Quantas vezes `payer` já usou o cupom `code`.
+    pub fn coupon_uses(env: Env, code: Symbol, payer: Address) -> u32 {
+        load_coupon_uses(&env, &code, &payer)
+    }
+
+    // -------------------- PROGRAMA DE INDICAÇÃO --------------------------------
+    /// Liga (ou altera) o programa de indicação (somente admin).
+    pub fn set_referral_config(env: Env, config: ReferralConfig) {
+        let admin = load_admin(&env);
+        admin.require_auth();
+        if let ReferralReward::CashbackBps(bps) = config.reward
+            && bps > BPS_DENOMINATOR
+        {
+            panic_with_error!(&env, Error::InvalidDiscount);
+        }
+        env.storage().instance().set(&DataKey::ReferralConfig, &config);
+        env.events()
+            .publish((Symbol::new(&env, "referral_config"),), config.reward);
+    }
+
+    /// Desliga o programa; indicações registradas são mantidas (somente admin).
+    pub fn clear_referral_config(env: Env) {
+        let admin = load_admin(&env);
+        admin.require_auth();
+        env.storage().instance().remove(&DataKey::ReferralConfig);
+        env.events()
+            .publish((Symbol::new(&env, "referral_config"),), ());
+    }
+
+    pub fn get_referral_config(env: Env) -> Option<ReferralConfig> {
+        env.storage().instance().get(&DataKey::ReferralConfig)
+    }
+
+    /// Registra, uma única vez, quem indicou `owner`. Recusa autoindicação e
+    /// indicação mútua (A indica B e B indica A).
+    pub fn register_referrer(env: Env, owner: Address, referrer: Address) {
+        owner.require_auth();
+        if load_referral(&env, &owner).is_some() {
+            panic_with_error!(&env, Error::AlreadyReferred);
         }
-        env.storage().instance().set(&DataKey::Admin, &admin);
-        env.storage().instance().set(&DataKey::Token, &token_asset);        
+        if referrer == owner
+            || load_referral(&env, &referrer).is_some_and(|r| r.referrer == owner)
+        {
+            panic_with_error!(&env, Error::SelfReferral);
+        }
+        env.storage().persistent().set(
+            &DataKey::Referral(owner.clone()),
+            &Referral {
+                referrer: referrer.clone(),
+                registered_at: env.ledger().timestamp(),
+                rewards: 0,
+            },
+        );
+        let mut stats = load_referral_stats(&env, &referrer);
+        stats.referees = stats.referees.saturating_add(1);
+        save_referral_stats(&env, &referrer, &stats);
         env.events()
-            .publish((symbol_short!("init"), admin.clone()), token_asset);
+            .publish((symbol_short!("referred"), referrer), owner);
     }
 
-        pub fn set_package(env: Env, id: u32, price: i128, duration_secs: u32, name: Symbol, speed_message: Symbol, is_popular: bool) {
-        let admin: Address = env
-            .storage()
-            .instance()
-            .get(&DataKey::Admin)
-            .ok_or(Error::NotInitialized)
-            .unwrap();
+    pub fn get_referral(env: Env, owner: Address) -> Option<Referral> {
+        load_referral(&env, &owner)
+    }
+
+    /// Indicados e recompensas acumuladas de `referrer`.
+    pub fn get_referral_stats(env: Env, referrer: Address) -> ReferralStats {
+        load_referral_stats(&env, &referrer)
+    }
+
+    /// Paga a `referrer` o cashback devido em `token` (admin ou
+    /// `Role::Treasurer`); sai da tesouraria, que também assina. Saldo
+    /// negativo (estorno depois do pagamento) fica para abater dos próximos.
+    /// Retorna o valor pago.
+    pub fn pay_cashback(env: Env, caller: Address, referrer: Address, token: Address) -> i128 {
+        require_role(&env, &caller, Role::Treasurer);
+        let due = get_cashback_due(&env, &referrer, &token);
+        if due <= 0 {
+            return 0;
+        }
+        let treasury = refund_funder(&env, &caller);
+        add_cashback_due(&env, &referrer, &token, -due);
+        TokenClient::new(&env, &token).transfer(&treasury, &referrer, &due);
+        env.events().publish(
+            (Symbol::new(&env, "cashback_paid"), referrer),
+            (due, token),
+        );
+        due
+    }
+
+    /// Cashback devido a `referrer` em `token` (negativo após estornos).
+    pub fn cashback_due(env: Env, referrer: Address, token: Address) -> i128 {
+        get_cashback_due(&env, &referrer, &token)
+    }
+
+    // -------------------- TESTE GRÁTIS -----------------------------------------
+    /// Define a exigência para resgatar o teste grátis (somente admin).
+    pub fn set_trial_gate(env: Env, gate: TrialGate) {
+        let admin = load_admin(&env);
         admin.require_auth();
-        let pkg = Package {
-            price,
-            duration_secs,
-            name,
-            speed_message,
-            is_popular,
-        };
-        env.storage().instance().set(&DataKey::Package(id), &pkg);
+        env.storage().instance().set(&DataKey::TrialGate, &gate);
         env.events()
-            .publish((symbol_short!("pkg_set"), id), (price, duration_secs));
+            .publish((Symbol::new(&env, "trial_gate"),), gate);
     }
 
-    // Add this function to the contract implementation
-    pub fn get_package(env: Env, package_id: u32) -> Package {
+    pub fn get_trial_gate(env: Env) -> TrialGate {
         env.storage()
             .instance()
-            .get(&DataKey::Package(package_id))
-            .unwrap_or_else(|| panic_with_error!(&env, Error::PackageNotFound))
+            .get(&DataKey::TrialGate)
+            .unwrap_or(TrialGate::Open)
     }
 
-    // Retorna informações completas dos pacotes creditados de um usuário
-    pub fn get_user_packages(env: Env, owner: Address) -> Vec<(u128, u32, bool)> {
-        let orders = get_user_orders_list(&env, &owner);
-        let mut packages = Vec::new(&env);
-        
-        for order_id in orders.iter() {
-            if let Some(order_rec) = load_order(&env, &owner, order_id) {
-                packages.push_back((order_id, order_rec.package_id, order_rec.credited));
+    /// Libera ou bloqueia `accounts` para o teste grátis no gate `Allowlist`
+    /// (somente admin).
+    pub fn set_trial_allowed(env: Env, accounts: Vec<Address>, allowed: bool) {
+        let admin = load_admin(&env);
+        admin.require_auth();
+        for account in accounts.iter() {
+            let key = DataKey::TrialAllowed(account);
+            if allowed {
+                env.storage().persistent().set(&key, &true);
+            } else {
+                env.storage().persistent().remove(&key);
             }
         }
-        
-        packages
     }
 
-    /// Retorna todos os pacotes cadastrados
-    pub fn get_all_packages(env: Env) -> Vec<(u32, Package)> {
-        let mut packages = Vec::new(&env);
-        
-        // Itera através de possíveis IDs de pacotes (máximo 10 pacotes)
-        for package_id in 1..=10u32 {
-            if let Some(package) = env.storage().instance().get(&DataKey::Package(package_id)) {
-                packages.push_back((package_id, package));
+    /// Resgata o pacote de teste `package_id` para `owner`: ordem creditada
+    /// sem cobrança, uma única vez por endereço. No gate `Attestation`,
+    /// `attestation` é a assinatura ed25519 de XDR((contrato, owner)).
+    pub fn claim_trial(env: Env, owner: Address, package_id: u32, attestation: Option<BytesN<64>>) -> u128 {
+        owner.require_auth();
+        ensure_not_paused(&env, Operation::Grants);
+        let claimed_key = DataKey::TrialClaimed(owner.clone());
+        if env.storage().persistent().has(&claimed_key) {
+            panic_with_error!(&env, Error::TrialAlreadyClaimed);
+        }
+        let pkg = load_package(&env, package_id);
+        if !pkg.is_trial {
+            panic_with_error!(&env, Error::TrialPackageMismatch);
+        }
+        if pkg.status != PackageStatus::Active {
+            panic_with_error!(&env, Error::PackageInactive);
+        }
+
+        match Self::get_trial_gate(env.clone()) {
+            TrialGate::Open => {}
+            TrialGate::Allowlist => {
+                if !env
+                    .storage()
+                    .persistent()
+                    .has(&DataKey::TrialAllowed(owner.clone()))
+                {
+                    panic_with_error!(&env, Error::Unauthorized);
+                }
+            }
+            TrialGate::Attestation(signer) => {
+                let Some(signature) = attestation else {
+                    panic_with_error!(&env, Error::Unauthorized);
+                };
+                let message = (env.current_contract_address(), owner.clone()).to_xdr(&env);
+                env.crypto().ed25519_verify(&signer, &message, &signature);
             }
         }
-        
-        packages
+
+        let order_id = open_credited_order(
+            &env,
+            &owner,
+            &OrderRec {
+                package_id,
+                status: OrderStatus::Credited,
+                price_paid: 0,
+                token: load_default_token(&env),
+                duration_secs: pkg.duration_secs,
+                purchased_at: env.ledger().timestamp(),
+                limits: pkg.limits.clone(),
+                payer: owner.clone(),
+            },
+        );
+        env.storage().persistent().set(&claimed_key, &order_id);
+        env.events()
+            .publish((symbol_short!("trial"), owner), (package_id, order_id));
+        order_id
     }
 
-    // -------------------- NOVO: buy_order (compra sem crédito) ----------------
+    /// Indica se `owner` já resgatou o teste grátis.
+    pub fn has_claimed_trial(env: Env, owner: Address) -> bool {
+        env.storage()
+            .persistent()
+            .has(&DataKey::TrialClaimed(owner))
+    }
 
-    fn dbg(env: &Env, step: &str) {
+    // -------------------- PROGRAMA DE FIDELIDADE -------------------------------
+    /// Liga (ou altera) o programa de fidelidade (somente admin). As faixas
+    /// devem ter `min_points` crescentes de bronze a ouro.
+    pub fn set_loyalty_config(env: Env, config: LoyaltyConfig) {
+        let admin = load_admin(&env);
+        admin.require_auth();
+        let perks = [&config.bronze, &config.silver, &config.gold];
+        let bps_ok = perks
+            .iter()
+            .all(|p| p.discount_bps <= BPS_DENOMINATOR && p.bonus_bps <= BPS_DENOMINATOR);
+        let order_ok = config.bronze.min_points <= config.silver.min_points
+            && config.silver.min_points <= config.gold.min_points;
+        if !bps_ok || !order_ok || config.points_per_sec < 0 {
+            panic_with_error!(&env, Error::InvalidLoyaltyConfig);
+        }
+        env.storage().instance().set(&DataKey::LoyaltyConfig, &config);
         env.events()
-            .publish((Symbol::new(env, "dbg"), Symbol::new(env, step)), ());
+            .publish((Symbol::new(&env, "loyalty_config"),), config.points_per_sec);
     }
 
-    pub fn buy_order(env: Env, owner: Address, package_id: u32) -> u128 {
-        owner.require_auth();
-        Self::dbg(&env, "start");
-
-        let admin: Address = env
-            .storage()
-            .instance()
-            .get(&DataKey::Admin)
-            .unwrap_or_else(|| {
-                Self::dbg(&env, "err_no_admin");
-                panic_with_error!(&env, Error::NotInitialized)
-            });
+    /// Desliga o programa; saldos de pontos são mantidos (somente admin).
+    pub fn clear_loyalty_config(env: Env) {
+        let admin = load_admin(&env);
+        admin.require_auth();
+        env.storage().instance().remove(&DataKey::LoyaltyConfig);
+        env.events()
+            .publish((Symbol::new(&env, "loyalty_config"),), ());
+    }
 
-        let token_id: Address = env
-            .storage()
-            .instance()
-            .get(&DataKey::Token)
-            .unwrap_or_else(|| {
-                Self::dbg(&env, "err_no_token");
-                panic_with_error!(&env, Error::NotInitialized)
-            });
+    pub fn get_loyalty_config(env: Env) -> Option<LoyaltyConfig> {
+        load_loyalty_config(&env)
+    }
 
-        let pkg: Package = env
-            .storage()
-            .instance()
-            .get(&DataKey::Package(package_id))
-            .unwrap_or_else(|| {
-                Self::dbg(&env, "err_pkg_nf");
-                panic_with_error!(&env, Error::PackageNotFound)
-            });
+    /// Saldo e total acumulado de pontos de `owner`.
+    pub fn get_loyalty(env: Env, owner: Address) -> LoyaltyAccount {
+        load_loyalty(&env, &owner)
+    }
 
-        Self::dbg(&env, "before_transfer");
-        let token = TokenClient::new(&env, &token_id);
-        token.transfer(&owner, &admin, &pkg.price); // se der erro do SAC, diagnostics mostram
+    /// Faixa de fidelidade atual de `owner`.
+    pub fn loyalty_tier(env: Env, owner: Address) -> LoyaltyTier {
+        match load_loyalty_config(&env) {
+            Some(config) => tier_for(&config, load_loyalty(&env, &owner).lifetime),
+            None => LoyaltyTier::None,
+        }
+    }
 
-        Self::dbg(&env, "after_transfer");
+    /// Troca pontos por uma ordem creditada do pacote `package_id`
+    /// (custo = duração × `points_per_sec`). Resgates não rendem pontos e
+    /// não valem para pacotes de teste.
+    pub fn redeem_points(env: Env, owner: Address, package_id: u32) -> u128 {
+        owner.require_auth();
+        ensure_not_paused(&env, Operation::Grants);
+        let config = load_loyalty_config(&env)
+            .filter(|c| c.points_per_sec > 0)
+            .unwrap_or_else(|| panic_with_error!(&env, Error::LoyaltyDisabled));
+        let pkg = load_package(&env, package_id);
+        if pkg.status != PackageStatus::Active {
+            panic_with_error!(&env, Error::PackageInactive);
+        }
+        // pacotes de teste só saem por `claim_trial` (uma vez por endereço)
+        if pkg.is_trial {
+            panic_with_error!(&env, Error::TrialPackageMismatch);
+        }
 
-        // >>>>> ALTERAÇÃO: gerar order_id determinístico pelo contador <<<<<
-        let order_id: u128 = next_order_id(&env, &owner);
+        let cost = pkg.duration_secs as i128 * config.points_per_sec;
+        let mut account = load_loyalty(&env, &owner);
+        if account.points < cost {
+            panic_with_error!(&env, Error::InsufficientPoints);
+        }
+        account.points -= cost;
+        save_loyalty(&env, &owner, &account);
 
-        save_order(
+        let order_id = open_credited_order(
             &env,
             &owner,
-            order_id,
             &OrderRec {
                 package_id,
-                credited: false,
+                status: OrderStatus::Credited,
+                price_paid: 0,
+                token: load_default_token(&env),
+                duration_secs: pkg.duration_secs,
+                purchased_at: env.ledger().timestamp(),
+                limits: pkg.limits.clone(),
+                payer: owner.clone(),
             },
         );
-
-        // NOVO: adicionar order_id à lista do usuário
-        add_user_order(&env, &owner, order_id);
-
         env.events().publish(
-            (Symbol::new(&env, "purchase"), Symbol::new(&env, "created")),
-            (owner, package_id, order_id, pkg.price),
+            (Symbol::new(&env, "points_redeemed"), owner),
+            (package_id, order_id, cost, account.points),
         );
-        Self::dbg(&env, "done");
         order_id
     }
 
-    /// Compra e credita o pacote em uma única transação assinada pelo cliente
-    /// Unifica buy_order + grant para melhor UX (uma única assinatura)
-    pub fn buy_and_grant(env: Env, owner: Address, package_id: u32) -> u128 {
-        owner.require_auth();
-        Self::dbg(&env, "buy_grant_start");
-
-        let admin: Address = env
-            .storage()
+    // -------------------- REDE DE REVENDEDORES ----------------------------------
+    /// Define o desconto de atacado da faixa `tier` (admin ou `Role::Treasurer`).
+    pub fn set_reseller_tier(env: Env, caller: Address, tier: u32, discount_bps: u32) {
+        require_role(&env, &caller, Role::Treasurer);
+        if discount_bps > BPS_DENOMINATOR {
+            panic_with_error!(&env, Error::InvalidDiscount);
+        }
+        env.storage()
             .instance()
-            .get(&DataKey::Admin)
-            .unwrap_or_else(|| {
-                Self::dbg(&env, "err_no_admin");
-                panic_with_error!(&env, Error::NotInitialized)
-            });
+            .set(&DataKey::ResellerTier(tier), &discount_bps);
+        env.events()
+            .publish((Symbol::new(&env, "reseller_tier"), caller, tier), discount_bps);
+    }
 
-        let token_id: Address = env
-            .storage()
-            .instance()
-            .get(&DataKey::Token)
-            .unwrap_or_else(|| {
-                Self::dbg(&env, "err_no_token");
-                panic_with_error!(&env, Error::NotInitialized)
-            });
+    pub fn get_reseller_tier(env: Env, tier: u32) -> Option<u32> {
+        load_tier_discount(&env, tier)
+    }
 
-        let pkg: Package = env
-            .storage()
-            .instance()
-            .get(&DataKey::Package(package_id))
-            .unwrap_or_else(|| {
-                Self::dbg(&env, "err_pkg_nf");
-                panic_with_error!(&env, Error::PackageNotFound)
-            });
-
-        // 1. Transferir pagamento
-        Self::dbg(&env, "before_transfer");
-        let token = TokenClient::new(&env, &token_id);
-        token.transfer(&owner, &admin, &pkg.price);
-        Self::dbg(&env, "after_transfer");
-
-        // 2. Criar ordem
-        let order_id: u128 = next_order_id(&env, &owner);
-        
-        // 3. Salvar ordem já creditada
-        save_order(
+    /// Cadastra (ou muda de faixa) um revendedor, que passa a poder emitir
+    /// vouchers e usar `reseller_buy`. Somente admin.
+    pub fn register_reseller(env: Env, reseller: Address, tier: u32) {
+        let admin = load_admin(&env);
+        admin.require_auth();
+        if load_tier_discount(&env, tier).is_none() {
+            panic_with_error!(&env, Error::TierNotFound);
+        }
+        let registered_at = load_reseller(&env, &reseller)
+            .map(|info| info.registered_at)
+            .unwrap_or(env.ledger().timestamp());
+        env.storage().persistent().set(
+            &DataKey::Reseller(reseller.clone()),
+            &ResellerInfo { tier, registered_at },
+        );
+        env.events()
+            .publish((Symbol::new(&env, "reseller_added"), reseller), tier);
+    }
+
+    /// Descadastra o revendedor, encerrando suas permissões (somente admin).
+    /// As estatísticas de comissão são mantidas.
+    pub fn remove_reseller(env: Env, reseller: Address) {
+        let admin = load_admin(&env);
+        admin.require_auth();
+        env.storage()
+            .persistent()
+            .remove(&DataKey::Reseller(reseller.clone()));
+        env.events()
+            .publish((Symbol::new(&env, "reseller_removed"), reseller), ());
+    }
+
+    pub fn get_reseller(env: Env, reseller: Address) -> Option<ResellerInfo> {
+        load_reseller(&env, &reseller)
+    }
+
+    /// Revendedor cadastrado compra para `customer` pelo preço de atacado
+    /// da sua faixa. A ordem nasce creditada para o cliente, com o
+    /// revendedor como pagador; o desconto de atacado conta como comissão
+    /// (um cupom nos termos é aplicado depois e não entra na comissão).
+    pub fn reseller_buy(
+        env: Env,
+        reseller: Address,
+        customer: Address,
+        package_id: u32,
+        token: Address,
+        quote: Option<OracleQuote>,
+        terms: PurchaseTerms,
+    ) -> u128 {
+        reseller.require_auth();
+        ensure_not_paused(&env, Operation::Purchases);
+        ensure_not_paused(&env, Operation::Grants);
+        let Some(info) = load_reseller(&env, &reseller) else {
+            panic_with_error!(&env, Error::ResellerNotRegistered);
+        };
+        let discount_bps = load_tier_discount(&env, info.tier).unwrap_or(0);
+
+        let receipt = purchase(
             &env,
-            &owner,
-            order_id,
-            &OrderRec {
-                package_id,
-                credited: true, // ← JÁ CREDITADO!
+            &reseller,
+            &customer,
+            package_id,
+            &token,
+            &terms,
+            PurchaseOptions {
+                quote,
+                credit: true,
+                discount_bps,
+                bonus_bps: 0,
             },
         );
+        let commission = receipt.tier_discount;
+        record_reseller_sale(&env, &reseller, &token, 1, receipt.price, commission);
+        env.events().publish(
+            (symbol_short!("resale"), reseller, customer),
+            (receipt.order_id, receipt.price, commission, token),
+        );
+        receipt.order_id
+    }
 
-        // 4. Adicionar à lista do usuário
-        add_user_order(&env, &owner, order_id);
+    /// Comissões acumuladas do revendedor no token `token`.
+    pub fn get_reseller_stats(env: Env, reseller: Address, token: Address) -> ResellerStats {
+        load_reseller_stats(&env, &reseller, &token)
+    }
 
-        // 5. Creditar tempo na sessão geral (compatibilidade)
-        let mut s = load_session(&env, &owner);
-        s.remaining_secs = s.remaining_secs.saturating_add(pkg.duration_secs as u64);
-        save_session(&env, &owner, &s);
+    // -------------------- VOUCHERS PRÉ-PAGOS ------------------------------------
+    /// Emite vouchers do pacote `package_id`, um por hash sha256 de código
+    /// secreto aleatório de `MIN_VOUCHER_CODE_LEN`+ bytes (admin ou revendedor
+    /// cadastrado). O revendedor paga o preço do pacote em `token` por
+    /// voucher na emissão, com o desconto da sua faixa; o admin emite sem custo.
+    /// Devolve o total cobrado.
+    pub fn issue_vouchers(
+        env: Env,
+        caller: Address,
+        package_id: u32,
+        token: Address,
+        hashes: Vec<BytesN<32>>,
+        quote: Option<OracleQuote>,
+    ) -> i128 {
+        if caller != load_admin(&env) && load_reseller(&env, &caller).is_none() {
+            panic_with_error!(&env, Error::Unauthorized);
+        }
+        caller.require_auth();
+        ensure_not_paused(&env, Operation::Purchases);
+        if hashes.len() > MAX_VOUCHER_BATCH {
+            panic_with_error!(&env, Error::VoucherBatchTooLarge);
+        }
 
-        // 6. Creditar tempo na sessão específica da ordem
-        let mut order_session = load_order_session(&env, &owner, order_id);
-        order_session.remaining_secs = order_session.remaining_secs.saturating_add(pkg.duration_secs as u64);
-        save_order_session(&env, &owner, order_id, &order_session);
+        let pkg = load_package(&env, package_id);
+        if pkg.status != PackageStatus::Active {
+            panic_with_error!(&env, Error::PackageInactive);
+        }
+        if pkg.is_trial {
+            panic_with_error!(&env, Error::TrialPackageMismatch);
+        }
+        let is_admin = caller == load_admin(&env);
+        let (unit_price, unit_commission) = if is_admin {
+            (0, 0)
+        } else {
+            // revendedor cadastrado paga o preço de atacado da sua faixa
+            let list_price = purchase_price(&env, &pkg, &token, &quote);
+            let discount = bps_of(list_price, reseller_discount_bps(&env, &caller));
+            (list_price - discount, discount)
+        };
 
-        // 7. Emitir eventos
+        let now = env.ledger().timestamp();
+        for hash in hashes.iter() {
+            if load_voucher(&env, &hash).is_some() {
+                panic_with_error!(&env, Error::VoucherExists);
+            }
+            save_voucher(
+                &env,
+                &hash,
+                &Voucher {
+                    package_id,
+                    issuer: caller.clone(),
+                    token: token.clone(),
+                    price_paid: unit_price,
+                    duration_secs: pkg.duration_secs,
+                    limits: pkg.limits.clone(),
+                    issued_at: now,
+                    redeemed_by: None,
+                },
+            );
+        }
+
+        let total = unit_price * hashes.len() as i128;
+        if total > 0 {
+            pay_out(&env, &TokenClient::new(&env, &token), &caller, package_id, total);
+        }
+        // a venda de vouchers entra nas comissões como a de `reseller_buy`
+        if !is_admin {
+            let commission = unit_commission * hashes.len() as i128;
+            record_reseller_sale(&env, &caller, &token, hashes.len(), total, commission);
+        }
         env.events().publish(
-            (Symbol::new(&env, "purchase"), Symbol::new(&env, "completed")),
-            (owner.clone(), package_id, order_id, pkg.price),
+            (Symbol::new(&env, "vouchers_issued"), caller, package_id),
+            (hashes.len(), total, token),
+        );
+        total
+    }
+
+    /// Resgata o voucher cujo sha256 é `sha256(preimage)`, criando para
+    /// `owner` uma ordem já creditada com os termos fixados na emissão.
+    /// Cada voucher só pode ser resgatado uma vez. Códigos com menos de
+    /// `MIN_VOUCHER_CODE_LEN` bytes são recusados (`VoucherNotFound`): devem ser
+    /// aleatórios, pois o hash é público e o código vai aberto na transação.
+    pub fn redeem_voucher(env: Env, owner: Address, preimage: Bytes) -> u128 {
+        owner.require_auth();
+        ensure_not_paused(&env, Operation::Grants);
+        if preimage.len() < MIN_VOUCHER_CODE_LEN {
+            panic_with_error!(&env, Error::VoucherNotFound);
+        }
+
+        let hash: BytesN<32> = env.crypto().sha256(&preimage).into();
+        let mut voucher = load_voucher(&env, &hash)
+            .unwrap_or_else(|| panic_with_error!(&env, Error::VoucherNotFound));
+        if voucher.redeemed_by.is_some() {
+            panic_with_error!(&env, Error::VoucherRedeemed);
+        }
+        voucher.redeemed_by = Some(owner.clone());
+        save_voucher(&env, &hash, &voucher);
+
+        let order_id = open_credited_order(
+            &env,
+            &owner,
+            &OrderRec {
+                package_id: voucher.package_id,
+                status: OrderStatus::Credited,
+                price_paid: voucher.price_paid,
+                token: voucher.token.clone(),
+                duration_secs: voucher.duration_secs,
+                purchased_at: env.ledger().timestamp(),
+                limits: voucher.limits.clone(),
+                payer: voucher.issuer.clone(),
+            },
+        );
+        env.events().publish(
+            (symbol_short!("voucher"), owner, hash),
+            (voucher.package_id, order_id, voucher.issuer),
         );
-        emit_grant(&env, &owner, order_id, s.remaining_secs);
-        
-        Self::dbg(&env, "buy_grant_done");
         order_id
     }
 
-    // -------------------- NOVO: grant (owner OU admin) ------------------------
+    /// Voucher pelo hash do código (para conferência pelo revendedor).
+    pub fn get_voucher(env: Env, hash: BytesN<32>) -> Option<Voucher> {
+        load_voucher(&env, &hash)
+    }
+
+    // -------------------- NOVO: grant (owner, admin OU Granter) ---------------
     /// Credita os segundos do pacote na sessão do `owner` referentes à `order_id`.
-    /// Pode ser chamado pelo **owner** (self-serve) OU pelo **admin**.
+    /// Pode ser chamado pelo **owner** (self-serve), pelo **admin** ou por quem
+    /// tiver `Role::Granter` (suporte).
     /// - Idempotente: se já creditado, retorna erro `AlreadyGranted`.
     pub fn grant(env: Env, caller: Address, owner: Address, order_id: u128) {
-        // autoriza: caller deve ser admin OU o próprio owner
-        let admin: Address = env
-            .storage()
-            .instance()
-            .get(&DataKey::Admin)
-            .ok_or(Error::NotInitialized)
-            .unwrap();
-        if caller != admin && caller != owner {
+        // autoriza: caller deve ser o próprio owner, admin OU Granter
+        if caller != owner && !has_role_or_admin(&env, &caller, Role::Granter) {
             panic_with_error!(&env, Error::Unauthorized);
         }
         caller.require_auth();
+        ensure_not_paused(&env, Operation::Grants);
 
-        // busca ordem; precisa existir e não ter sido creditada
+        // busca ordem; precisa existir e estar Pending
         let mut ord = load_order(&env, &owner, order_id)
             .ok_or(Error::OrderNotFound)
             .unwrap();
-        if ord.credited {
-            panic_with_error!(&env, Error::AlreadyGranted);
+        require_status(&env, &ord, OrderStatus::Pending);
+
+        // credita exatamente os segundos comprados (snapshot da ordem)
+        let remaining = credit_time(&env, &owner, order_id, ord.duration_secs);
+
+        // marca como creditado e emite evento
+        transition_order(&env, &owner, order_id, &mut ord, OrderStatus::Credited);
+        emit_grant(&env, &owner, order_id, remaining);
+
+        // modo escrow: só agora o pagamento segue para a tesouraria
+        release_escrow(&env, &owner, order_id, &ord);
+        // pontos e indicação só rendem com a ordem creditada (compras de `buy_order`)
+        accrue_points(&env, &owner, order_id, &ord.token, ord.price_paid);
+        reward_referral(&env, &owner, order_id, &ord);
+    }
+
+    // -------------------- reembolso de ordens não creditadas ------------------
+    /// O dono pede reembolso de uma ordem ainda não creditada.
+    /// Em modo escrow, passado `RefundTimeout` desde a compra, o valor retido
+    /// volta na hora (retorna `true`); senão o pedido aguarda `approve_refund`.
+    pub fn request_refund(env: Env, owner: Address, order_id: u128) -> bool {
+        owner.require_auth();
+        let mut ord = load_refundable_order(&env, &owner, order_id);
+        let now = env.ledger().timestamp();
+
+        let timeout: u64 = env
+            .storage()
+            .instance()
+            .get(&DataKey::RefundTimeout)
+            .unwrap_or(0);
+        // ordem em disputa espera a decisão do admin
+        if timeout > 0
+            && ord.status == OrderStatus::Pending
+            && load_escrow(&env, &owner, order_id) > 0
+            && now >= ord.purchased_at.saturating_add(timeout)
+        {
+            refund_order(&env, &owner, order_id, &mut ord, &owner);
+            return true;
         }
 
-        // pega pacote vinculado à ordem
-        let pkg: Package = env
+        env.storage()
+            .persistent()
+            .set(&DataKey::RefundRequest(owner.clone(), order_id), &now);
+        env.events()
+            .publish((Symbol::new(&env, "refund_requested"), owner), order_id);
+        false
+    }
+
+    /// Aprova um pedido de reembolso (admin ou `Role::Treasurer`). O valor sai
+    /// do escrow da ordem; sem escrow, sai da tesouraria, que também assina.
+    pub fn approve_refund(env: Env, caller: Address, owner: Address, order_id: u128) {
+        require_role(&env, &caller, Role::Treasurer);
+        let mut ord = load_refundable_order(&env, &owner, order_id);
+        if !env
             .storage()
+            .persistent()
+            .has(&DataKey::RefundRequest(owner.clone(), order_id))
+        {
+            panic_with_error!(&env, Error::RefundNotRequested);
+        }
+        refund_order(&env, &owner, order_id, &mut ord, &caller);
+    }
+
+    /// Timestamp do pedido de reembolso pendente (se houver).
+    pub fn get_refund_request(env: Env, owner: Address, order_id: u128) -> Option<u64> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::RefundRequest(owner, order_id))
+    }
+
+    /// Define após quantos segundos da compra uma ordem em escrow é
+    /// reembolsada automaticamente no `request_refund` (0 = desligado).
+    /// Admin ou `Role::Treasurer`.
+    pub fn set_refund_timeout(env: Env, caller: Address, timeout_secs: u64) {
+        require_role(&env, &caller, Role::Treasurer);
+        env.storage()
             .instance()
-            .get(&DataKey::Package(ord.package_id))
-            .ok_or(Error::PackageNotFound)
-            .unwrap();
+            .set(&DataKey::RefundTimeout, &timeout_secs);
+        env.events()
+            .publish((Symbol::new(&env, "refund_timeout"), caller), timeout_secs);
+    }
 
-        // credita tempo na sessão geral (mantém compatibilidade)
-        let mut s = load_session(&env, &owner);
-        s.remaining_secs = s.remaining_secs.saturating_add(pkg.duration_secs as u64);
-        save_session(&env, &owner, &s);
+    // -------------------- reembolso proporcional do tempo não usado -----------
+    /// Define a política de reembolso proporcional de um pacote (admin ou `Role::Treasurer`).
+    pub fn set_refund_policy(env: Env, caller: Address, package_id: u32, policy: RefundPolicy) {
+        require_role(&env, &caller, Role::Treasurer);
+        if policy.percent_bps > BPS_DENOMINATOR || policy.fee < 0 {
+            panic_with_error!(&env, Error::InvalidRefundPolicy);
+        }
+        env.storage()
+            .persistent()
+            .set(&DataKey::RefundPolicy(package_id), &policy);
+        env.events()
+            .publish((Symbol::new(&env, "refund_policy"), package_id), policy);
+    }
+
+    /// Remove a política de reembolso proporcional de um pacote (admin ou `Role::Treasurer`).
+    pub fn clear_refund_policy(env: Env, caller: Address, package_id: u32) {
+        require_role(&env, &caller, Role::Treasurer);
+        env.storage()
+            .persistent()
+            .remove(&DataKey::RefundPolicy(package_id));
+        env.events()
+            .publish((Symbol::new(&env, "refund_policy"), package_id), ());
+    }
+
+    pub fn get_refund_policy(env: Env, package_id: u32) -> Option<RefundPolicy> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::RefundPolicy(package_id))
+    }
+
+    /// Reembolsa os segundos não usados de uma ordem creditada, conforme a
+    /// política do pacote. Precisa da assinatura do dono e de um admin ou
+    /// `Role::Treasurer`; a tesouraria (que também assina) paga o reembolso a
+    /// quem pagou a ordem. Pausa e zera a sessão da ordem.
+    /// Retorna o valor devolvido.
+    pub fn refund_unused(env: Env, caller: Address, owner: Address, order_id: u128) -> i128 {
+        require_role(&env, &caller, Role::Treasurer);
+        owner.require_auth();
+
+        let mut ord = load_order(&env, &owner, order_id)
+            .unwrap_or_else(|| panic_with_error!(&env, Error::OrderNotFound));
+        require_refund_status(&env, &owner, order_id, &ord, OrderStatus::Credited);
+        require_known_price(&env, &ord);
+        let policy: RefundPolicy = env
+            .storage()
+            .persistent()
+            .get(&DataKey::RefundPolicy(ord.package_id))
+            .unwrap_or_else(|| panic_with_error!(&env, Error::NoRefundPolicy));
 
-        // credita tempo na sessão específica da ordem
+        let now = env.ledger().timestamp();
         let mut order_session = load_order_session(&env, &owner, order_id);
-        order_session.remaining_secs = order_session.remaining_secs.saturating_add(pkg.duration_secs as u64);
+        let remaining = remaining_at_order(&env, &order_session, now);
+        if remaining < policy.min_remaining_secs {
+            panic_with_error!(&env, Error::RemainingTooLow);
+        }
+        let amount = prorata_refund_amount(&ord, &policy, remaining);
+
+        // zera a sessão da ordem e retira os mesmos segundos da sessão geral
+        order_session.remaining_secs = 0;
+        order_session.started_at = 0;
         save_order_session(&env, &owner, order_id, &order_session);
+        let mut s = load_session(&env, &owner);
+        s.remaining_secs = s.remaining_secs.saturating_sub(remaining);
+        save_session(&env, &owner, &s);
 
-        // marca como creditado e emite evento
-        ord.credited = true;
-        save_order(&env, &owner, order_id, &ord);
-        emit_grant(&env, &owner, order_id, s.remaining_secs);
+        if amount > 0 {
+            TokenClient::new(&env, &ord.token).transfer(&refund_funder(&env, &caller), &ord.payer, &amount);
+        }
+        forfeit_points(&env, &owner, order_id, amount, ord.price_paid);
+        forfeit_referral(&env, &owner, order_id, &ord.token, amount, ord.price_paid);
+        transition_order(&env, &owner, order_id, &mut ord, OrderStatus::Refunded);
+        env.events().publish(
+            (symbol_short!("prorata"), owner),
+            (order_id, remaining, amount),
+        );
+        amount
     }
 
     // -------------------- start / pause / getters (inalterados) ---------------
     pub fn start(env: Env, owner: Address) {
         owner.require_auth();
+        ensure_not_paused(&env, Operation::SessionStarts);
         let now = env.ledger().timestamp();
         let mut s = load_session(&env, &owner);
         if remaining_at(&env, &s, now) == 0 {
@@ -413,21 +2729,21 @@ impl ConectaBrasil {
      /// Inicia uma sessão específica por order_id
     pub fn start_order(env: Env, owner: Address, order_id: u128) {
         owner.require_auth();
+        ensure_not_paused(&env, Operation::SessionStarts);
         let now = env.ledger().timestamp();
         
-        // Verifica se a ordem existe e foi creditada
-        let order = load_order(&env, &owner, order_id)
+        // Verifica se a ordem existe e está creditada
+        let mut order = load_order(&env, &owner, order_id)
             .ok_or(Error::OrderNotFound)
             .unwrap();
-        if !order.credited {
-            panic_with_error!(&env, Error::AlreadyGranted);
-        }
+        require_status(&env, &order, OrderStatus::Credited);
         
         // Carrega a sessão da ordem
         let mut order_session = load_order_session(&env, &owner, order_id);
         
-        // Verifica se há tempo restante
+        // Sem tempo restante a ordem se esgotou
         if remaining_at_order(&env, &order_session, now) == 0 {
+            transition_order(&env, &owner, order_id, &mut order, OrderStatus::Exhausted);
             return;
         }
         
@@ -457,12 +2773,74 @@ impl ConectaBrasil {
             order_session.started_at = 0;
             save_order_session(&env, &owner, order_id, &order_session);
             env.events().publish(
-                (Symbol::new(&env, "pause_order"), owner),
+                (Symbol::new(&env, "pause_order"), owner.clone()),
                 (order_id, order_session.remaining_secs)
             );
+
+            // pausou sem saldo: a ordem se esgotou
+            if order_session.remaining_secs == 0
+                && let Some(mut order) = load_order(&env, &owner, order_id)
+                && order.status == OrderStatus::Credited
+            {
+                transition_order(&env, &owner, order_id, &mut order, OrderStatus::Exhausted);
+            }
+        }
+    }
+
+    /// Muda o estado de uma ordem manualmente (somente admin): cancelar,
+    /// expirar, abrir disputa ou resolvê-la. Crédito, reembolso e esgotamento
+    /// têm fluxos próprios (`grant`, reembolsos, sessão) e não passam por aqui.
+    /// Cancelar ou expirar só vale para ordens sem nada a reembolsar (sem
+    /// escrow e sem valor pago); as demais saem por `approve_refund`.
+    /// Uma disputa só volta ao estado que a ordem tinha antes dela; uma disputa
+    /// aberta a partir de Pending também pode ser cancelada (nas mesmas
+    /// condições), e reembolsos seguem por `approve_refund` / `refund_unused`.
+    pub fn set_order_status(env: Env, owner: Address, order_id: u128, status: OrderStatus) {
+        let admin = load_admin(&env);
+        admin.require_auth();
+        let mut ord = load_order(&env, &owner, order_id)
+            .unwrap_or_else(|| panic_with_error!(&env, Error::OrderNotFound));
+        // disputas sem registro (anteriores a DisputedFrom) só podem ser canceladas
+        let disputed_from = if ord.status == OrderStatus::Disputed {
+            load_disputed_from(&env, &owner, order_id)
+        } else {
+            None
+        };
+
+        let allowed = match status {
+            OrderStatus::Disputed => true,
+            OrderStatus::Cancelled | OrderStatus::Expired => {
+                // só sai sem reembolso quem não tem nada a reembolsar: sem
+                // escrow e sem valor pago à tesouraria (ordens legadas, com
+                // valor desconhecido, não têm reembolso on-chain)
+                if load_escrow(&env, &owner, order_id) > 0
+                    || (ord.price_paid > 0 && ord.purchased_at > 0)
+                {
+                    panic_with_error!(&env, Error::EscrowHeld);
+                }
+                disputed_from != Some(OrderStatus::Credited)
+            }
+            OrderStatus::Pending | OrderStatus::Credited => disputed_from == Some(status),
+            OrderStatus::Refunded | OrderStatus::Exhausted => false,
+        };
+        if !allowed {
+            panic_with_error!(&env, Error::InvalidOrderTransition);
+        }
+        let from = ord.status;
+        transition_order(&env, &owner, order_id, &mut ord, status);
+        if status == OrderStatus::Disputed {
+            env.storage()
+                .persistent()
+                .set(&DataKey::DisputedFrom(owner, order_id), &from);
         }
     }
 
+    /// Retorna o registro de uma ordem (termos comprados e estado do crédito)
+    pub fn get_order(env: Env, owner: Address, order_id: u128) -> OrderRec {
+        load_order(&env, &owner, order_id)
+            .unwrap_or_else(|| panic_with_error!(&env, Error::OrderNotFound))
+    }
+
         /// Retorna a sessão específica de uma ordem
     pub fn get_order_session(env: Env, owner: Address, order_id: u128) -> OrderSession {
         load_order_session(&env, &owner, order_id)
@@ -1,4 +1,7 @@
 #![no_std]
 
 pub mod model;
-pub mod conecta_brasil_contract;
\ No newline at end of file
+pub mod oracle;
+pub mod conecta_brasil_contract;
+#[cfg(test)]
+mod test;
@@ -1,18 +1,62 @@
-use soroban_sdk::{contracterror, contracttype, Address, Symbol};
+use soroban_sdk::{contracterror, contracttype, Address, BytesN, Map, String, Symbol, Vec};
 
 // -------------------------------------------------------------
 // MODELO DE DADOS
 // -------------------------------------------------------------
 
+/// Ciclo de vida de um pacote no catálogo.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum PackageStatus {
+    Active,   // à venda
+    Inactive, // fora de venda temporariamente (pode voltar com reactivate_package)
+    Retired,  // removido do catálogo; registro mantido p/ ordens existentes
+}
+
 /// Um "pacote" de internet: preço (em unidades do token, ex.: XLM/SAC) e duração (segundos).
 #[derive(Clone)]
 #[contracttype]
 pub struct Package {
-    pub price: i128,        // preço em unidades do token (ex.: stroops se for XLM/SAC)
+    pub price: i128,        // preço em unidades do token padrão (ex.: stroops se for XLM/SAC)
     pub duration_secs: u32, // duração total concedida ao comprar este pacote
-    pub name: Symbol,       // nome descritivo do pacote (ex.: "Básico", "Premium")
-    pub speed_message: Symbol, // mensagem sobre a velocidade (ex.: "Até 10 Mbps", "Velocidade máxima")
+    pub name: String,       // nome descritivo do pacote (ex.: "Básico", "Premium")
+    pub speed_message: String, // mensagem sobre a velocidade (ex.: "Até 10 Mbps", "Velocidade máxima")
+    pub description: Option<String>, // texto livre opcional exibido na vitrine
     pub is_popular: bool,   // indica se é o pacote mais popular/usado
+    pub status: PackageStatus, // apenas pacotes Active podem ser comprados
+    pub limits: PackageLimits, // atributos técnicos aplicados por roteadores/portais
+    pub token_prices: Map<Address, i128>, // preços em outros tokens aceitos (token -> preço)
+    pub ref_price: Option<i128>, // preço na moeda de referência (centavos); convertido via oráculo
+    pub is_trial: bool,     // teste grátis: só via claim_trial, uma vez por endereço, sem cobrança
+}
+
+/// Atributos técnicos de um pacote, lidos on-chain pelos pontos de controle
+/// (roteador, captive portal). Em todos os campos, 0 = sem limite.
+#[derive(Clone)]
+#[contracttype]
+pub struct PackageLimits {
+    pub download_kbps: u32,  // velocidade máxima de download
+    pub upload_kbps: u32,    // velocidade máxima de upload
+    pub data_cap_bytes: u64, // franquia de dados durante a ordem
+    pub max_devices: u32,    // dispositivos simultâneos
+}
+
+/// Idiomas suportados para os textos de vitrine.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum Locale {
+    PtBr,
+    En,
+    Es,
+}
+
+/// Textos de um pacote traduzidos para um `Locale`.
+#[derive(Clone)]
+#[contracttype]
+pub struct PackageText {
+    pub name: String,
+    pub speed_message: String,
+    pub description: Option<String>,
 }
 
 /// Estado de sessão com "saldo de segundos" e marcador de início:
@@ -43,26 +87,323 @@ pub struct Access {
     pub expires_at: u64, // se pausado, 0; se ativo, started_at + remaining_secs
 }
 
+/// Estado de uma ordem. Transições válidas:
+/// - Pending  -> Credited | Refunded | Cancelled | Expired | Disputed
+/// - Credited -> Exhausted | Refunded | Disputed
+/// - Disputed -> Pending | Credited | Refunded | Cancelled (resolução pelo admin)
+/// - Refunded / Cancelled / Expired / Exhausted são finais
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum OrderStatus {
+    Pending,   // paga, aguardando grant
+    Credited,  // segundos creditados na sessão
+    Refunded,  // valor (ou tempo não usado) devolvido
+    Cancelled, // cancelada pelo admin sem crédito
+    Expired,   // não creditada dentro do prazo
+    Exhausted, // todo o tempo creditado foi consumido
+    Disputed,  // em disputa, aguardando resolução do admin
+}
+
 /// Registro de ordem de compra (paga on-chain, mas ainda não creditada).
 /// Usado para separar COMPRA (buy_order) do CRÉDITO (grant) com idempotência.
+/// Os termos do pacote são copiados na compra: o grant credita exatamente o
+/// que foi pago, mesmo que o pacote mude ou seja removido depois.
 #[derive(Clone)]
 #[contracttype]
 pub struct OrderRec {
     pub package_id: u32, // pacote comprado
-    pub credited: bool,  // se os segundos já foram creditados na sessão
+    pub status: OrderStatus, // estado da ordem (ver transições em OrderStatus)
+    pub price_paid: i128,   // valor pago, em unidades de `token`
+    pub token: Address,     // token usado no pagamento
+    pub duration_secs: u32, // segundos comprados (creditados no grant)
+    pub purchased_at: u64,  // unix ts (ledger) da compra; 0 = ordem anterior ao snapshot
+    pub limits: PackageLimits, // direitos técnicos comprados (snapshot do pacote)
+    pub payer: Address,        // quem pagou (difere do dono em presentes via buy_for)
+}
+
+/// Papéis delegáveis pelo admin. O admin sempre possui todos os papéis.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum Role {
+    CatalogManager, // cria/edita pacotes (set_package)
+    Granter,        // credita ordens pagas de qualquer usuário (grant)
+    Treasurer,      // operações financeiras
+    Pauser,         // aciona o circuit breaker
+}
+
+/// Operações que podem ser suspensas pelo circuit breaker.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum Operation {
+    Purchases,     // buy_order / buy_and_grant
+    Grants,        // grant / buy_and_grant
+    SessionStarts, // start / start_order (pause continua sempre liberado)
+}
+
+/// Estado do circuit breaker; `true` = operação suspensa.
+#[derive(Clone)]
+#[contracttype]
+pub struct PauseState {
+    pub purchases: bool,
+    pub grants: bool,
+    pub session_starts: bool,
+}
+
+/// Parte da receita de uma venda destinada a `recipient`, em basis points
+/// (10000 = 100%). Uma regra de divisão é um Vec<SplitShare> somando 10000.
+#[derive(Clone)]
+#[contracttype]
+pub struct SplitShare {
+    pub recipient: Address,
+    pub bps: u32,
+}
+
+/// Política de reembolso proporcional do tempo não usado de um pacote.
+/// Valor devolvido = preço pago × (segundos restantes / segundos comprados)
+/// × `percent_bps` / 10000 − `fee`.
+#[derive(Clone)]
+#[contracttype]
+pub struct RefundPolicy {
+    pub percent_bps: u32,        // fração do valor proporcional devolvida (10000 = 100%)
+    pub min_remaining_secs: u64, // abaixo disso não há reembolso
+    pub fee: i128,               // taxa fixa descontada, em unidades do token da ordem
+}
+
+/// Oráculo usado para converter preços na moeda de referência (ex.: BRL)
+/// para unidades de token no momento da compra.
+#[derive(Clone)]
+#[contracttype]
+pub struct OracleConfig {
+    pub oracle: Address,  // contrato com interface SEP-40 (lastprice)
+    pub currency: Symbol, // moeda de referência cotada como Asset::Other (ex.: "BRL")
+}
+
+/// Limites aceitos pelo comprador para compras com preço via oráculo.
+#[derive(Clone)]
+#[contracttype]
+pub struct OracleQuote {
+    pub max_staleness_secs: u64, // idade máxima das cotações usadas
+    pub expected_amount: i128,   // valor visto pelo comprador (ex.: via `quote`)
+    pub max_slippage_bps: u32,   // tolerância acima de expected_amount
+}
+
+/// Termos aceitos pelo comprador em uma compra (None = sem checagem/cupom).
+/// O `OracleQuote` dos pacotes com preço de referência vai em argumento próprio.
+#[derive(Clone)]
+#[contracttype]
+pub struct PurchaseTerms {
+    pub max_price: Option<i128>,             // aborta se o valor a cobrar for maior
+    pub expected_duration_secs: Option<u32>, // aborta se a duração do pacote mudou
+    pub coupon: Option<Symbol>,              // cupom promocional (ver set_coupon)
+}
+
+/// Voucher pré-pago vendido fora da chain (ex.: ambulante recebe em dinheiro).
+/// Guardado pelo sha256 do código secreto; os termos são fixados na emissão.
+#[derive(Clone)]
+#[contracttype]
+pub struct Voucher {
+    pub package_id: u32,
+    pub issuer: Address,                // quem emitiu (e pagou, se não for o admin)
+    pub token: Address,                 // token usado no pagamento da emissão
+    pub price_paid: i128,               // valor pago por voucher (0 = cortesia do admin)
+    pub duration_secs: u32,             // snapshot do pacote na emissão
+    pub limits: PackageLimits,          // snapshot do pacote na emissão
+    pub issued_at: u64,                 // unix ts da emissão
+    pub redeemed_by: Option<Address>,   // Some = já resgatado
+}
+
+/// Revendedor aprovado pelo admin (ambulantes, lojas).
+#[derive(Clone)]
+#[contracttype]
+pub struct ResellerInfo {
+    pub tier: u32,          // faixa de atacado (desconto em DataKey::ResellerTier)
+    pub registered_at: u64, // unix ts do cadastro
+}
+
+/// Contabilidade de vendas de um revendedor em um token. A comissão é a
+/// diferença entre o preço de tabela e o valor pago no atacado.
+#[derive(Clone)]
+#[contracttype]
+pub struct ResellerStats {
+    pub orders: u32,        // vendas: ordens via reseller_buy + vouchers emitidos
+    pub retail_value: i128, // soma dos preços de tabela
+    pub paid: i128,         // soma do que o revendedor pagou
+    pub commission: i128,   // retail_value - paid
+}
+
+/// Desconto de um cupom: percentual em bps ou valor fixo em um token
+/// (só vale para compras nesse token).
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum CouponDiscount {
+    Percent(u32),
+    Fixed(Address, i128),
+}
+
+/// Regras de um cupom promocional, definidas pelo admin em `set_coupon`.
+#[derive(Clone)]
+#[contracttype]
+pub struct CouponRules {
+    pub discount: CouponDiscount,
+    pub package_ids: Vec<u32>,  // pacotes aceitos (vazio = todos)
+    pub starts_at: u64,         // unix ts de início da validade
+    pub ends_at: u64,           // unix ts de fim da validade (0 = sem fim)
+    pub max_uses: u32,          // usos totais permitidos (0 = ilimitado)
+    pub max_uses_per_user: u32, // usos por pagador (0 = ilimitado)
+}
+
+/// Cupom promocional criado pelo admin.
+#[derive(Clone)]
+#[contracttype]
+pub struct Coupon {
+    pub rules: CouponRules,
+    pub uses: u32,    // usos até agora
+    pub active: bool, // false = desativado pelo admin
+}
+
+/// Recompensa do indicador por compra qualificada do indicado.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum ReferralReward {
+    BonusSecs(u32),   // segundos creditados em uma ordem bônus do indicador
+    CashbackBps(u32), // parte do valor pago devida ao indicador (paga pela tesouraria)
+}
+
+/// Regras do programa de indicação (ausente = programa desligado).
+#[derive(Clone)]
+#[contracttype]
+pub struct ReferralConfig {
+    pub reward: ReferralReward,
+    pub min_price: i128,              // valor mínimo cobrado para a compra qualificar
+    pub max_rewards_per_referee: u32, // compras recompensadas por indicado (0 = ilimitado)
+}
+
+/// Indicação registrada por um comprador (uma única vez).
+#[derive(Clone)]
+#[contracttype]
+pub struct Referral {
+    pub referrer: Address,
+    pub registered_at: u64,
+    pub rewards: u32, // compras deste indicado que já renderam recompensa
+}
+
+/// Estatísticas de um indicador.
+#[derive(Clone)]
+#[contracttype]
+pub struct ReferralStats {
+    pub referees: u32,                // compradores que o indicaram
+    pub rewarded_purchases: u32,      // compras que renderam recompensa
+    pub bonus_secs: u64,              // segundos bônus recebidos
+    pub cashback: Map<Address, i128>, // token -> cashback creditado (líquido de estornos)
+}
+
+/// Recompensa que uma ordem rendeu ao indicador (estornada no reembolso).
+#[derive(Clone)]
+#[contracttype]
+pub struct ReferralPayout {
+    pub referrer: Address,
+    pub bonus_order: Option<u128>, // ordem bônus do indicador (BonusSecs)
+    pub bonus_secs: u32,
+    pub cashback: i128, // no token da ordem
+}
+
+/// Faixa de fidelidade, definida pelos pontos acumulados na vida
+/// (resgates não rebaixam a faixa).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum LoyaltyTier {
+    None,
+    Bronze,
+    Silver,
+    Gold,
+}
+
+/// Benefícios de uma faixa de fidelidade.
+#[derive(Clone)]
+#[contracttype]
+pub struct TierPerk {
+    pub min_points: i128,  // pontos acumulados para entrar na faixa
+    pub discount_bps: u32, // desconto no preço das compras
+    pub bonus_bps: u32,    // segundos extras sobre a duração do pacote
+}
+
+/// Regras do programa de fidelidade (ausente = programa desligado).
+#[derive(Clone)]
+#[contracttype]
+pub struct LoyaltyConfig {
+    pub accrual_bps: Map<Address, u32>, // token -> pontos por unidade paga, em bps (ausente = não pontua)
+    pub bronze: TierPerk,
+    pub silver: TierPerk,
+    pub gold: TierPerk,
+    pub points_per_sec: i128,           // custo em pontos de cada segundo resgatado (0 = sem resgate)
+}
+
+/// Saldo de pontos de um usuário.
+#[derive(Clone)]
+#[contracttype]
+pub struct LoyaltyAccount {
+    pub points: i128,   // saldo disponível para resgate (negativo se um estorno alcançou pontos já resgatados)
+    pub lifetime: i128, // total acumulado (define a faixa)
+}
+
+/// Exigência para resgatar o teste grátis (limita contas sybil).
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum TrialGate {
+    Open,                    // qualquer endereço
+    Allowlist,               // só endereços liberados pelo admin (set_trial_allowed)
+    Attestation(BytesN<32>), // assinatura ed25519 desta chave sobre (contrato, owner)
 }
 
 /// Chaves de armazenamento:
-/// - Instance storage: Admin / Token / Package / NextOrder
-///   (config/global + contador determinístico por dono)
+/// - Instance storage: Admin / PendingAdmin / PauseState / SchemaVersion / Token / Treasury
+///   / AcceptedTokens / Oracle / Split / EscrowMode / RefundTimeout / ResellerTier
+///   / ReferralConfig / LoyaltyConfig / TrialGate
+///   (config/global, de tamanho fixo)
 /// - Persistent storage:
+///     - Package(id) / PackageIds  -> catálogo (instance no schema v1)
+///     - PackageText(id, locale)   -> traduções do pacote
+///     - PackageSplit(id)          -> divisão de receita do pacote
+///     - RefundPolicy(id)          -> política de reembolso proporcional do pacote
+///     - Role(role, addr)          -> papel concedido
+///     - NextOrder(owner)          -> contador determinístico por dono (instance no schema v1)
 ///     - Session(owner)            -> estado por usuário (vida longa)
 ///     - Order(owner, order_id)    -> ordem paga, pendente ou já creditada
+///     - Escrow(owner, order_id)   -> valor retido no contrato até o grant
+///     - RefundRequest(owner, order_id) -> pedido de reembolso aguardando aprovação
+///     - DisputedFrom(owner, order_id) -> estado da ordem antes da disputa
+///     - Voucher(hash)             -> voucher pré-pago (sha256 do código)
+///     - Reseller(addr)            -> cadastro do revendedor (única fonte da permissão de revenda)
+///     - ResellerStats(addr, token) -> comissões acumuladas
+///     - Coupon(code)              -> cupom promocional
+///     - CouponUses(code, payer)   -> usos do cupom por pagador
+///     - Referral(owner)           -> indicador registrado pelo comprador
+///     - ReferralStats(referrer)   -> recompensas acumuladas do indicador
+///     - OrderReferral(owner, order_id) -> recompensa de indicação rendida pela ordem
+///     - CashbackDue(referrer, token) -> cashback ainda não pago pela tesouraria
+///     - Loyalty(owner)            -> pontos de fidelidade
+///     - OrderPoints(owner, order_id) -> pontos rendidos pela ordem
+///     - TrialAllowed(owner)       -> liberado para o teste grátis (gate Allowlist)
+///     - TrialClaimed(owner)       -> teste grátis já resgatado
 #[contracttype]
 pub enum DataKey {
     Admin,        // Address do administrador do catálogo
-    Token,        // Address do contrato do token (SAC) usado na cobrança
+    PendingAdmin, // Address proposto para assumir o Admin (troca em duas etapas)
+    Role(Role, Address), // (papel, conta) -> bool
+    PauseState,   // PauseState do circuit breaker (ausente = nada suspenso)
+    SchemaVersion, // u32 do layout de storage (ausente = v1, layout original)
+    Token,        // Address do contrato do token (SAC) padrão usado na cobrança
+    AcceptedTokens, // Vec<Address> de tokens aceitos além do padrão
+    Oracle,       // OracleConfig p/ pacotes com preço em moeda de referência
+    Treasury,     // Address que recebe a receita (ausente = admin)
+    Split,        // Vec<SplitShare> global (ausente = 100% para a tesouraria)
+    PackageSplit(u32), // id -> Vec<SplitShare> (tem precedência sobre a global)
+    EscrowMode,   // bool: compras via buy_order ficam retidas até o grant
+    RefundTimeout, // u64: segundos após a compra p/ reembolso automático em escrow (0 = desligado)
+    RefundPolicy(u32), // package_id -> RefundPolicy (ausente = sem reembolso proporcional)
     Package(u32), // id -> Package
+    PackageIds,   // Vec<u32> ordenado com os ids do catálogo
+    PackageText(u32, Locale), // (id, idioma) -> PackageText
     // contador sequencial por dono para gerar order_id determinístico
     NextOrder(Address),   // owner -> u128 (próximo order_id disponível)
     // lista de ordens por usuário
@@ -70,6 +411,26 @@ pub enum DataKey {
     Session(Address),     // owner -> Session (mantido para compatibilidade)
     OrderSession(Address, u128), // (owner, order_id) -> OrderSession
     Order(Address, u128), // (owner, order_id) -> OrderRec
+    Escrow(Address, u128), // (owner, order_id) -> i128 retido no contrato
+    RefundRequest(Address, u128), // (owner, order_id) -> u64 (ts do pedido)
+    DisputedFrom(Address, u128), // (owner, order_id) -> OrderStatus anterior à disputa
+    Voucher(BytesN<32>),  // sha256(código) -> Voucher
+    ResellerTier(u32),    // faixa -> u32 (desconto de atacado em bps)
+    Reseller(Address),    // revendedor -> ResellerInfo
+    ResellerStats(Address, Address), // (revendedor, token) -> ResellerStats
+    Coupon(Symbol),       // código -> Coupon
+    CouponUses(Symbol, Address), // (código, pagador) -> u32
+    ReferralConfig,       // ReferralConfig (ausente = sem programa de indicação)
+    Referral(Address),    // indicado -> Referral
+    ReferralStats(Address), // indicador -> ReferralStats
+    OrderReferral(Address, u128), // (owner, order_id) -> ReferralPayout
+    CashbackDue(Address, Address), // (indicador, token) -> i128 (negativo após estorno)
+    LoyaltyConfig,        // LoyaltyConfig (ausente = sem programa de fidelidade)
+    Loyalty(Address),     // owner -> LoyaltyAccount
+    OrderPoints(Address, u128), // (owner, order_id) -> i128 (estornados no reembolso)
+    TrialGate,            // TrialGate (ausente = Open)
+    TrialAllowed(Address), // owner -> bool
+    TrialClaimed(Address), // owner -> u128 (order_id do teste grátis)
 }
 
 // -------------------------------------------------------------
@@ -87,4 +448,83 @@ pub enum Error {
     // fluxo buy_order + grant
     OrderNotFound = 6,  // ordem não existe (ex.: order_id inválido)
     AlreadyGranted = 7, // ordem já foi creditada (idempotência no grant)
+
+    // troca de admin em duas etapas
+    NoPendingAdmin = 8, // não há troca de admin proposta
+
+    // circuit breaker
+    ContractPaused = 9, // operação suspensa pelo admin/Pauser
+
+    // upgrade / migração
+    InvalidSchemaVersion = 10, // storage está em versão mais nova que este WASM
+
+    // ciclo de vida de pacotes
+    PackageInactive = 11, // pacote fora de venda (Inactive/Retired)
+    PackageRetired = 12,  // pacote removido não pode ser editado nem reativado
+
+    // metadados (limites em bytes UTF-8)
+    NameTooLong = 13,
+    SpeedMessageTooLong = 14,
+    DescriptionTooLong = 15,
+
+    // divisão de receita
+    InvalidSplit = 16, // regra vazia, com parte zerada ou que não soma 10000 bps
+
+    // reembolso
+    OrderRefunded = 17,      // ordem já reembolsada (não pode ser creditada)
+    RefundNotRequested = 18, // aprovação sem pedido do dono
+    InvalidRefundPolicy = 19, // percent_bps > 10000 ou fee negativa
+    NoRefundPolicy = 20,      // pacote não aceita reembolso proporcional, ou ordem legada com valor pago desconhecido
+    RemainingTooLow = 21,     // saldo abaixo de min_remaining_secs
+    OrderNotCredited = 22,    // operação exige ordem já creditada
+
+    // máquina de estados da ordem
+    InvalidOrderTransition = 23, // transição de OrderStatus não permitida
+    EscrowHeld = 24,             // ordem com valor pago (escrow ou tesouraria) deve sair pelo reembolso
+
+    // múltiplos tokens
+    TokenNotAccepted = 25, // token fora da allowlist
+    NoPriceForToken = 26,  // pacote sem preço no token escolhido
+
+    // preço via oráculo
+    PriceUnavailable = 27, // oráculo não configurado, sem cotação, conversão fora do i128 ou ref_price negativo
+    StalePrice = 28,       // cotação mais velha que max_staleness_secs
+    SlippageExceeded = 29, // valor convertido acima do tolerado pelo comprador
+    QuoteRequired = 30,    // pacote com preço de referência exige OracleQuote
+
+    // proteção do comprador
+    PriceAboveMax = 31,    // valor a cobrar acima do max_price do comprador
+    DurationMismatch = 32, // duração do pacote diferente da esperada pelo comprador
+
+    // vouchers pré-pagos
+    VoucherExists = 33,        // hash já emitido
+    VoucherNotFound = 34,      // código não corresponde a nenhum voucher (ou é curto demais)
+    VoucherRedeemed = 35,      // voucher já resgatado
+    VoucherBatchTooLarge = 36, // lote acima de MAX_VOUCHER_BATCH
+
+    // rede de revendedores
+    ResellerNotRegistered = 37, // endereço fora do cadastro de revendedores
+    TierNotFound = 38,          // faixa de atacado não configurada
+    InvalidDiscount = 39,       // desconto acima de 10000 bps (ou fixo negativo)
+
+    // cupons
+    CouponNotFound = 40,      // código inexistente
+    CouponNotValid = 41,      // cupom desativado ou fora da janela de validade
+    CouponNotApplicable = 42, // pacote ou token fora das regras do cupom
+    CouponExhausted = 43,     // limite global ou por usuário atingido
+
+    // indicações
+    AlreadyReferred = 44, // comprador já registrou um indicador
+    SelfReferral = 45,    // autoindicação ou indicação mútua
+
+    // fidelidade
+    LoyaltyDisabled = 46,      // programa desligado ou sem resgate
+    InsufficientPoints = 47,   // saldo de pontos menor que o custo do resgate
+    InvalidLoyaltyConfig = 48, // bps acima de 10000 ou faixas fora de ordem
+
+    // teste grátis (gate recusado usa Unauthorized)
+    // obs.: #[contracterror] aceita no máximo 50 variantes; novos erros
+    // precisam reaproveitar os existentes
+    TrialPackageMismatch = 49, // pacote de teste só sai por claim_trial (e sempre grátis); claim_trial só aceita pacote de teste
+    TrialAlreadyClaimed = 50,  // endereço já resgatou o teste grátis
 }
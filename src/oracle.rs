@@ -0,0 +1,58 @@
+use soroban_sdk::{contractclient, contracttype, Address, Env, Symbol};
+
+// -------------------------------------------------------------
+// INTERFACE DE ORÁCULO DE PREÇOS (estilo SEP-40)
+// -------------------------------------------------------------
+
+/// Ativo cotado pelo oráculo: token Stellar (contrato) ou símbolo externo (ex.: "BRL").
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum Asset {
+    Stellar(Address),
+    Other(Symbol),
+}
+
+/// Cotação de um ativo na moeda base do oráculo.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct PriceData {
+    pub price: i128,
+    pub timestamp: u64, // unix ts da cotação
+}
+
+/// Subconjunto da interface SEP-40 usado pelo contrato.
+#[contractclient(name = "PriceOracleClient")]
+pub trait PriceOracle {
+    fn lastprice(env: Env, asset: Asset) -> Option<PriceData>;
+}
+
+// -------------------------------------------------------------
+// ORÁCULO FALSO (somente em testes ou com a feature `testutils`)
+// -------------------------------------------------------------
+#[cfg(any(test, feature = "testutils"))]
+pub mod mock {
+    use super::{Asset, PriceData};
+    use soroban_sdk::{contract, contractimpl, contracttype, Env};
+
+    #[contracttype]
+    enum MockKey {
+        Price(Asset),
+    }
+
+    /// Oráculo com cotações definidas manualmente, para testes com testutils.
+    #[contract]
+    pub struct MockOracle;
+
+    #[contractimpl]
+    impl MockOracle {
+        pub fn set_price(env: Env, asset: Asset, price: i128, timestamp: u64) {
+            env.storage()
+                .instance()
+                .set(&MockKey::Price(asset), &PriceData { price, timestamp });
+        }
+
+        pub fn lastprice(env: Env, asset: Asset) -> Option<PriceData> {
+            env.storage().instance().get(&MockKey::Price(asset))
+        }
+    }
+}
@@ -0,0 +1,149 @@
+use super::*;
+use crate::model::{CouponDiscount, CouponRules};
+use soroban_sdk::{Symbol, Vec, vec};
+
+fn with_coupon(code: &Symbol) -> PurchaseTerms {
+    PurchaseTerms {
+        coupon: Some(code.clone()),
+        ..no_terms()
+    }
+}
+
+fn rules(env: &Env, discount: CouponDiscount) -> CouponRules {
+    CouponRules {
+        discount,
+        package_ids: Vec::new(env),
+        starts_at: 0,
+        ends_at: 0,
+        max_uses: 0,
+        max_uses_per_user: 0,
+    }
+}
+
+#[test]
+fn coupon_caps_are_global_and_per_payer() {
+    let t = setup();
+    add_package(&t, 1, 1000, 3600);
+    let code = Symbol::new(&t.env, "PROMO10");
+    t.client.set_coupon(
+        &code,
+        &CouponRules {
+            max_uses: 2,
+            max_uses_per_user: 1,
+            ..rules(&t.env, CouponDiscount::Percent(1000))
+        },
+    );
+    let [a, b, c] = [0; 3].map(|_| funded(&t, 2000));
+
+    let order_id = t
+        .client
+        .buy_order(&a, &1, &t.token, &None, &with_coupon(&code));
+    assert_eq!(t.client.get_order(&a, &order_id).price_paid, 900);
+    assert_eq!(balance(&t, &a), 1100);
+    assert_eq!(
+        t.client
+            .try_buy_order(&a, &1, &t.token, &None, &with_coupon(&code)),
+        Err(Ok(Error::CouponExhausted.into()))
+    );
+    t.client
+        .buy_order(&b, &1, &t.token, &None, &with_coupon(&code));
+    assert_eq!(
+        t.client
+            .try_buy_order(&c, &1, &t.token, &None, &with_coupon(&code)),
+        Err(Ok(Error::CouponExhausted.into()))
+    );
+    assert_eq!(t.client.coupon_uses(&code, &a), 1);
+    assert_eq!(t.client.coupon_uses(&code, &c), 0);
+    assert_eq!(t.client.get_coupon(&code).unwrap().uses, 2);
+}
+
+#[test]
+fn coupon_applies_only_within_its_window_and_packages() {
+    let t = setup();
+    add_package(&t, 1, 1000, 3600);
+    add_package(&t, 2, 1000, 3600);
+    let code = Symbol::new(&t.env, "JANELA");
+    t.client.set_coupon(
+        &code,
+        &CouponRules {
+            package_ids: vec![&t.env, 2],
+            starts_at: 11_000,
+            ends_at: 12_000,
+            ..rules(&t.env, CouponDiscount::Percent(5000))
+        },
+    );
+    let buyer = funded(&t, 10_000);
+
+    assert_eq!(
+        t.client
+            .try_buy_order(&buyer, &2, &t.token, &None, &with_coupon(&code)),
+        Err(Ok(Error::CouponNotValid.into()))
+    );
+    t.env.ledger().set_timestamp(11_000);
+    assert_eq!(
+        t.client
+            .try_buy_order(&buyer, &1, &t.token, &None, &with_coupon(&code)),
+        Err(Ok(Error::CouponNotApplicable.into()))
+    );
+    let order_id = t
+        .client
+        .buy_order(&buyer, &2, &t.token, &None, &with_coupon(&code));
+    assert_eq!(t.client.get_order(&buyer, &order_id).price_paid, 500);
+
+    t.env.ledger().set_timestamp(12_001);
+    assert_eq!(
+        t.client
+            .try_buy_order(&buyer, &2, &t.token, &None, &with_coupon(&code)),
+        Err(Ok(Error::CouponNotValid.into()))
+    );
+
+    t.env.ledger().set_timestamp(11_500);
+    t.client.disable_coupon(&code);
+    assert_eq!(
+        t.client
+            .try_buy_order(&buyer, &2, &t.token, &None, &with_coupon(&code)),
+        Err(Ok(Error::CouponNotValid.into()))
+    );
+}
+
+#[test]
+fn fixed_coupon_only_applies_to_its_token() {
+    let t = setup();
+    add_package(&t, 1, 1000, 3600);
+    let other = t
+        .env
+        .register_stellar_asset_contract_v2(t.admin.clone())
+        .address();
+    t.client.add_token(&t.admin, &other);
+    t.client.set_package_price(&t.admin, &1, &other, &1000);
+    let code = Symbol::new(&t.env, "FIXO300");
+    t.client.set_coupon(
+        &code,
+        &rules(&t.env, CouponDiscount::Fixed(t.token.clone(), 300)),
+    );
+    let buyer = funded(&t, 1000);
+    StellarAssetClient::new(&t.env, &other).mint(&buyer, &1000);
+
+    assert_eq!(
+        t.client
+            .try_buy_order(&buyer, &1, &other, &None, &with_coupon(&code)),
+        Err(Ok(Error::CouponNotApplicable.into()))
+    );
+    assert_eq!(t.client.coupon_uses(&code, &buyer), 0);
+    let order_id = t
+        .client
+        .buy_order(&buyer, &1, &t.token, &None, &with_coupon(&code));
+    assert_eq!(t.client.get_order(&buyer, &order_id).price_paid, 700);
+
+    // desconto fixo acima do preço zera a compra, sem passar disso
+    let big = Symbol::new(&t.env, "FIXO5000");
+    t.client.set_coupon(
+        &big,
+        &rules(&t.env, CouponDiscount::Fixed(t.token.clone(), 5000)),
+    );
+    let order_id = t
+        .client
+        .buy_order(&buyer, &1, &t.token, &None, &with_coupon(&big));
+    assert_eq!(t.client.get_order(&buyer, &order_id).price_paid, 0);
+    assert_eq!(balance(&t, &buyer), 300);
+}
@@ -0,0 +1,68 @@
+use super::*;
+use crate::model::{OrderStatus, SplitShare};
+use soroban_sdk::vec;
+
+#[test]
+fn escrow_holds_payment_until_grant_releases_it_through_the_split() {
+    let t = setup();
+    add_package(&t, 1, 1000, 3600);
+    let platform = Address::generate(&t.env);
+    let isp = Address::generate(&t.env);
+    t.client.set_split(
+        &Some(1),
+        &vec![
+            &t.env,
+            SplitShare {
+                recipient: platform.clone(),
+                bps: 7000,
+            },
+            SplitShare {
+                recipient: isp.clone(),
+                bps: 3000,
+            },
+        ],
+    );
+    t.client.set_escrow_mode(&t.admin, &true);
+
+    let buyer = funded(&t, 1000);
+    let order_id = t.client.buy_order(&buyer, &1, &t.token, &None, &no_terms());
+    assert_eq!(t.client.get_escrow(&buyer, &order_id), 1000);
+    assert_eq!(balance(&t, &t.client.address), 1000);
+    assert_eq!(balance(&t, &buyer), 0);
+    assert_eq!(balance(&t, &platform), 0);
+
+    // com valor retido a ordem só sai pelo grant ou pelo reembolso
+    for status in [OrderStatus::Cancelled, OrderStatus::Expired] {
+        assert_eq!(
+            t.client.try_set_order_status(&buyer, &order_id, &status),
+            Err(Ok(Error::EscrowHeld.into()))
+        );
+    }
+
+    t.client.grant(&t.admin, &buyer, &order_id);
+    assert_eq!(t.client.get_escrow(&buyer, &order_id), 0);
+    assert_eq!(balance(&t, &t.client.address), 0);
+    assert_eq!(balance(&t, &platform), 700);
+    assert_eq!(balance(&t, &isp), 300);
+    assert_eq!(
+        t.client.get_order(&buyer, &order_id).status,
+        OrderStatus::Credited
+    );
+}
+
+#[test]
+fn buy_order_without_escrow_pays_the_treasury_at_purchase() {
+    let t = setup();
+    add_package(&t, 1, 1000, 3600);
+    let treasury = Address::generate(&t.env);
+    t.client.set_treasury(&treasury);
+
+    let buyer = funded(&t, 1000);
+    let order_id = t.client.buy_order(&buyer, &1, &t.token, &None, &no_terms());
+    assert_eq!(t.client.get_escrow(&buyer, &order_id), 0);
+    assert_eq!(balance(&t, &treasury), 1000);
+    assert_eq!(
+        t.client.get_order(&buyer, &order_id).status,
+        OrderStatus::Pending
+    );
+}
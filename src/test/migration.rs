@@ -0,0 +1,162 @@
+use super::*;
+use crate::conecta_brasil_contract::SCHEMA_VERSION;
+use crate::model::{DataKey, OrderStatus, PackageStatus};
+use soroban_sdk::{Bytes, BytesN, Map, Symbol, Val, Vec, vec};
+
+// WASM do deploy original (schema v1, commit 58a392b), compilado com
+// `cargo build --release --target wasm32v1-none` a partir de `git archive
+// 58a392b`, acrescentando ao Cargo.toml `crate-type = ["cdylib"]` e o
+// `[profile.release]` padrão dos contratos Soroban (opt-level "z", lto,
+// panic "abort").
+mod v1 {
+    soroban_sdk::contractimport!(file = "src/test/fixtures/conecta_brasil_v1.wasm");
+}
+
+// troca o código de `contract_id` direto pelo host, como faria um upgrade:
+// o v1 não exporta `upgrade`. Devolve o hash que volta ao código nativo
+// (contratos nativos ficam registrados com o hash do WASM vazio).
+fn install_v1(env: &Env, contract_id: &Address) -> BytesN<32> {
+    let v1_hash = env.deployer().upload_contract_wasm(v1::WASM);
+    env.as_contract(contract_id, || {
+        let native_hash = env.deployer().upload_contract_wasm(Bytes::new(env));
+        env.deployer().update_current_contract_wasm(v1_hash);
+        native_hash
+    })
+}
+
+fn reinstall_native(env: &Env, contract_id: &Address, native_hash: &BytesN<32>) {
+    env.as_contract(contract_id, || {
+        env.deployer()
+            .update_current_contract_wasm(native_hash.clone())
+    });
+}
+
+#[test]
+fn migrate_upgrades_v1_storage() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().set_timestamp(10_000);
+    let admin = Address::generate(&env);
+    let owner = Address::generate(&env);
+    let token = env
+        .register_stellar_asset_contract_v2(admin.clone())
+        .address();
+    StellarAssetClient::new(&env, &token).mint(&owner, &2_000);
+    let contract_id = env.register(ConectaBrasil, ());
+    let client = ConectaBrasilClient::new(&env, &contract_id);
+
+    // dados gravados pelo próprio v1
+    let native_hash = install_v1(&env, &contract_id);
+    let old = v1::Client::new(&env, &contract_id);
+    old.init(&admin, &token);
+    old.set_package(
+        &1,
+        &100,
+        &3600,
+        &Symbol::new(&env, "Basico"),
+        &symbol_short!("10Mbps"),
+        &false,
+    );
+    old.set_package(
+        &70,
+        &900,
+        &3600,
+        &Symbol::new(&env, "Mensal_10GB"),
+        &symbol_short!("10Mbps"),
+        &true,
+    );
+    assert_eq!(old.buy_and_grant(&owner, &1), 1);
+    assert_eq!(old.buy_order(&owner, &70), 2);
+
+    // o código novo lê o storage do v1 como ficou, antes de migrar
+    reinstall_native(&env, &contract_id, &native_hash);
+    assert_eq!(client.schema_version(), 1);
+    env.as_contract(&contract_id, || {
+        let instance = env.storage().instance();
+        assert_eq!(
+            instance.get::<_, Address>(&DataKey::Admin),
+            Some(admin.clone())
+        );
+        assert_eq!(
+            instance.get::<_, Address>(&DataKey::Token),
+            Some(token.clone())
+        );
+        assert_eq!(
+            instance.get::<_, u128>(&DataKey::NextOrder(owner.clone())),
+            Some(2)
+        );
+        let raw: Map<Symbol, Val> = instance.get(&DataKey::Package(70)).unwrap();
+        assert_eq!(raw.len(), 5);
+        assert!(!instance.has(&DataKey::SchemaVersion));
+        assert!(!env.storage().persistent().has(&DataKey::PackageIds));
+    });
+
+    // o pacote 70 está fora da faixa sondada e vem em `legacy_package_ids`
+    assert_eq!(
+        client.migrate(&vec![&env, owner.clone()], &vec![&env, 70]),
+        SCHEMA_VERSION
+    );
+    assert_eq!(client.schema_version(), SCHEMA_VERSION);
+
+    let packages = client.get_packages(&None, &10);
+    assert_eq!(packages.len(), 2);
+    let (id, basico) = packages.get_unchecked(0);
+    assert_eq!(id, 1);
+    assert_eq!(basico.name, String::from_str(&env, "Basico"));
+    assert_eq!(basico.speed_message, String::from_str(&env, "10Mbps"));
+    assert_eq!(basico.description, None);
+    assert_eq!(basico.status, PackageStatus::Active);
+    assert!(!basico.is_trial);
+    let (id, mensal) = packages.get_unchecked(1);
+    assert_eq!(id, 70);
+    assert_eq!(mensal.name, String::from_str(&env, "Mensal_10GB"));
+    assert!(mensal.is_popular);
+    // o catálogo saiu de instance
+    env.as_contract(&contract_id, || {
+        for id in [1u32, 70] {
+            assert!(!env.storage().instance().has(&DataKey::Package(id)));
+            assert!(env.storage().persistent().has(&DataKey::Package(id)));
+        }
+        assert!(
+            !env.storage()
+                .instance()
+                .has(&DataKey::NextOrder(owner.clone()))
+        );
+    });
+
+    // ordens antigas herdam os termos atuais do pacote e o dono como pagador
+    let credited = client.get_order(&owner, &1);
+    assert_eq!(credited.status, OrderStatus::Credited);
+    assert_eq!(credited.price_paid, 100);
+    assert_eq!(credited.duration_secs, 3600);
+    assert_eq!(credited.token, token);
+    assert_eq!(credited.payer, owner);
+    assert_eq!(credited.purchased_at, 0);
+    let pending = client.get_order(&owner, &2);
+    assert_eq!(pending.status, OrderStatus::Pending);
+    assert_eq!(pending.price_paid, 900);
+    // valor pago só estimado: sem reembolso on-chain
+    assert_eq!(
+        client.try_request_refund(&owner, &2),
+        Err(Ok(Error::NoRefundPolicy.into()))
+    );
+
+    // o contador continua de onde o v1 parou
+    let next = client.buy_order(&owner, &1, &token, &None, &no_terms());
+    assert_eq!(next, 3);
+    assert_eq!(client.get_order(&owner, &3).status, OrderStatus::Pending);
+
+    // rodar de novo não altera nada
+    assert_eq!(
+        client.migrate(&vec![&env, owner.clone()], &Vec::new(&env)),
+        SCHEMA_VERSION
+    );
+    assert_eq!(client.get_order(&owner, &1).status, OrderStatus::Credited);
+    assert_eq!(client.get_order(&owner, &2).price_paid, 900);
+
+    // um upgrade pelo próprio contrato mantém o storage migrado
+    client.upgrade(&native_hash);
+    assert_eq!(client.schema_version(), SCHEMA_VERSION);
+    assert_eq!(client.get_packages(&None, &10).len(), 2);
+    assert_eq!(client.get_order(&owner, &3).price_paid, 100);
+}
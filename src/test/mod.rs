@@ -0,0 +1,88 @@
+extern crate std;
+
+mod coupons;
+mod escrow;
+mod migration;
+mod oracle;
+mod order_status;
+mod referrals;
+mod refunds;
+mod split;
+mod trial;
+
+use crate::conecta_brasil_contract::{ConectaBrasil, ConectaBrasilClient};
+use crate::model::{Error, PackageText, PurchaseTerms};
+use crate::oracle::mock::{MockOracle, MockOracleClient};
+use soroban_sdk::testutils::{Address as _, Ledger};
+use soroban_sdk::token::{StellarAssetClient, TokenClient};
+use soroban_sdk::{Address, Env, String, symbol_short};
+
+struct Setup<'a> {
+    env: Env,
+    admin: Address,
+    token: Address,
+    oracle: MockOracleClient<'a>,
+    client: ConectaBrasilClient<'a>,
+}
+
+fn setup<'a>() -> Setup<'a> {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().set_timestamp(10_000);
+
+    let admin = Address::generate(&env);
+    let token = env
+        .register_stellar_asset_contract_v2(admin.clone())
+        .address();
+    let client = ConectaBrasilClient::new(&env, &env.register(ConectaBrasil, ()));
+    client.init(&admin, &token);
+
+    let oracle = MockOracleClient::new(&env, &env.register(MockOracle, ()));
+    client.set_oracle(&admin, &oracle.address, &symbol_short!("BRL"));
+    Setup {
+        env,
+        admin,
+        token,
+        oracle,
+        client,
+    }
+}
+
+fn package_text(env: &Env, name: &str) -> PackageText {
+    PackageText {
+        name: String::from_str(env, name),
+        speed_message: String::from_str(env, "10 Mbps"),
+        description: None,
+    }
+}
+
+fn no_terms() -> PurchaseTerms {
+    PurchaseTerms {
+        max_price: None,
+        expected_duration_secs: None,
+        coupon: None,
+    }
+}
+
+// pacote de preço fixo no token padrão
+fn add_package(t: &Setup, id: u32, price: i128, duration_secs: u32) {
+    t.client.set_package(
+        &t.admin,
+        &id,
+        &price,
+        &duration_secs,
+        &package_text(&t.env, "Pacote"),
+        &false,
+    );
+}
+
+// conta nova com `amount` do token padrão
+fn funded(t: &Setup, amount: i128) -> Address {
+    let account = Address::generate(&t.env);
+    StellarAssetClient::new(&t.env, &t.token).mint(&account, &amount);
+    account
+}
+
+fn balance(t: &Setup, account: &Address) -> i128 {
+    TokenClient::new(&t.env, &t.token).balance(account)
+}
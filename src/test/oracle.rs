@@ -0,0 +1,119 @@
+use super::*;
+use crate::model::OracleQuote;
+use crate::oracle::Asset;
+
+// cotações com 14 casas, como nos oráculos SEP-40 usuais
+const BRL_USD: i128 = 18_000_000_000_000; // 0,18
+const XLM_USD: i128 = 9_000_000_000_000; // 0,09
+
+// pacote de R$ 10,00 cotado a BRL 0,18 / XLM 0,09 em `quoted_at`
+fn setup_ref_priced(quoted_at: u64) -> Setup<'static> {
+    let t = setup();
+    t.client.set_package(
+        &t.admin,
+        &1,
+        &0,
+        &3600,
+        &package_text(&t.env, "Hora"),
+        &false,
+    );
+    t.client.set_package_ref_price(&t.admin, &1, &Some(1000));
+    t.oracle
+        .set_price(&Asset::Other(symbol_short!("BRL")), &BRL_USD, &quoted_at);
+    t.oracle
+        .set_price(&Asset::Stellar(t.token.clone()), &XLM_USD, &quoted_at);
+    t
+}
+
+#[test]
+fn ref_price_converts_at_oracle_cross_rate() {
+    let t = setup_ref_priced(10_000);
+    // 10 BRL × 0,18 / 0,09 = 20 XLM (7 casas)
+    assert_eq!(t.client.quote(&1, &t.token), 20_0000000);
+
+    let buyer = Address::generate(&t.env);
+    StellarAssetClient::new(&t.env, &t.token).mint(&buyer, &50_0000000);
+    let quote = OracleQuote {
+        max_staleness_secs: 60,
+        expected_amount: 20_0000000,
+        max_slippage_bps: 0,
+    };
+    let order_id = t
+        .client
+        .buy_and_grant(&buyer, &1, &t.token, &Some(quote), &no_terms());
+    assert_eq!(t.client.get_order(&buyer, &order_id).price_paid, 20_0000000);
+    assert_eq!(
+        TokenClient::new(&t.env, &t.token).balance(&buyer),
+        30_0000000
+    );
+}
+
+#[test]
+fn stale_oracle_price_is_rejected() {
+    let t = setup_ref_priced(9_000);
+    let buyer = Address::generate(&t.env);
+    let quote = OracleQuote {
+        max_staleness_secs: 300,
+        expected_amount: 20_0000000,
+        max_slippage_bps: 100,
+    };
+    assert_eq!(
+        t.client
+            .try_buy_and_grant(&buyer, &1, &t.token, &Some(quote), &no_terms()),
+        Err(Ok(Error::StalePrice.into()))
+    );
+}
+
+#[test]
+fn price_above_buyer_tolerance_is_rejected() {
+    let t = setup_ref_priced(10_000);
+    let buyer = Address::generate(&t.env);
+    // o comprador viu 18 XLM e tolera 5% (até 18,9); o oráculo agora dá 20
+    let quote = OracleQuote {
+        max_staleness_secs: 60,
+        expected_amount: 18_0000000,
+        max_slippage_bps: 500,
+    };
+    assert_eq!(
+        t.client
+            .try_buy_and_grant(&buyer, &1, &t.token, &Some(quote), &no_terms()),
+        Err(Ok(Error::SlippageExceeded.into()))
+    );
+}
+
+#[test]
+fn oracle_overflow_is_price_unavailable() {
+    let t = setup_ref_priced(10_000);
+    // cotação absurda: a conta estouraria o i128 em vez de dar um valor pequeno
+    t.oracle.set_price(
+        &Asset::Other(symbol_short!("BRL")),
+        &(i128::MAX / 2),
+        &10_000,
+    );
+    assert_eq!(
+        t.client.try_quote(&1, &t.token),
+        Err(Ok(Error::PriceUnavailable.into()))
+    );
+}
+
+#[test]
+fn ref_price_rejects_negative_and_quote_checks_token() {
+    let t = setup_ref_priced(10_000);
+    assert_eq!(
+        t.client.try_set_package_ref_price(&t.admin, &1, &Some(-1)),
+        Err(Ok(Error::PriceUnavailable.into()))
+    );
+
+    let other = t
+        .env
+        .register_stellar_asset_contract_v2(t.admin.clone())
+        .address();
+    t.oracle
+        .set_price(&Asset::Stellar(other.clone()), &XLM_USD, &10_000);
+    assert_eq!(
+        t.client.try_quote(&1, &other),
+        Err(Ok(Error::TokenNotAccepted.into()))
+    );
+    t.client.add_token(&t.admin, &other);
+    assert_eq!(t.client.quote(&1, &other), 20_0000000);
+}
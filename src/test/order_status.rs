@@ -0,0 +1,140 @@
+use super::*;
+use crate::model::OrderStatus;
+
+fn status_of(t: &Setup, owner: &Address, order_id: u128) -> OrderStatus {
+    t.client.get_order(owner, &order_id).status
+}
+
+fn assert_rejected(t: &Setup, owner: &Address, order_id: u128, to: OrderStatus, err: Error) {
+    assert_eq!(
+        t.client.try_set_order_status(owner, &order_id, &to),
+        Err(Ok(err.into()))
+    );
+}
+
+#[test]
+fn manual_status_changes_follow_the_state_machine() {
+    let t = setup();
+    add_package(&t, 1, 0, 3600);
+    add_package(&t, 2, 1000, 3600);
+    let buyer = funded(&t, 1000);
+
+    let free = t.client.buy_order(&buyer, &1, &t.token, &None, &no_terms());
+    for to in [
+        OrderStatus::Refunded,
+        OrderStatus::Exhausted,
+        OrderStatus::Credited,
+    ] {
+        assert_rejected(&t, &buyer, free, to, Error::InvalidOrderTransition);
+    }
+    t.client
+        .set_order_status(&buyer, &free, &OrderStatus::Cancelled);
+    assert_eq!(status_of(&t, &buyer, free), OrderStatus::Cancelled);
+    // estado final
+    for to in [
+        OrderStatus::Pending,
+        OrderStatus::Disputed,
+        OrderStatus::Expired,
+    ] {
+        assert_rejected(&t, &buyer, free, to, Error::InvalidOrderTransition);
+    }
+
+    // creditada não expira nem é cancelada
+    let credited = t
+        .client
+        .buy_and_grant(&buyer, &1, &t.token, &None, &no_terms());
+    assert_rejected(
+        &t,
+        &buyer,
+        credited,
+        OrderStatus::Expired,
+        Error::InvalidOrderTransition,
+    );
+
+    // paga e sem escrow: só sai pelo reembolso
+    let paid = t.client.buy_order(&buyer, &2, &t.token, &None, &no_terms());
+    assert_rejected(&t, &buyer, paid, OrderStatus::Cancelled, Error::EscrowHeld);
+    assert_rejected(&t, &buyer, paid, OrderStatus::Expired, Error::EscrowHeld);
+    assert_eq!(status_of(&t, &buyer, paid), OrderStatus::Pending);
+}
+
+#[test]
+fn disputes_resolve_back_to_the_prior_status() {
+    let t = setup();
+    add_package(&t, 1, 0, 3600);
+    add_package(&t, 2, 1000, 3600);
+    let buyer = funded(&t, 2000);
+
+    let credited = t
+        .client
+        .buy_and_grant(&buyer, &2, &t.token, &None, &no_terms());
+    t.client
+        .set_order_status(&buyer, &credited, &OrderStatus::Disputed);
+    assert_rejected(
+        &t,
+        &buyer,
+        credited,
+        OrderStatus::Pending,
+        Error::InvalidOrderTransition,
+    );
+    t.client
+        .set_order_status(&buyer, &credited, &OrderStatus::Credited);
+    assert_eq!(status_of(&t, &buyer, credited), OrderStatus::Credited);
+
+    let pending = t.client.buy_order(&buyer, &1, &t.token, &None, &no_terms());
+    t.client
+        .set_order_status(&buyer, &pending, &OrderStatus::Disputed);
+    assert_rejected(
+        &t,
+        &buyer,
+        pending,
+        OrderStatus::Credited,
+        Error::InvalidOrderTransition,
+    );
+    t.client
+        .set_order_status(&buyer, &pending, &OrderStatus::Pending);
+    assert_eq!(status_of(&t, &buyer, pending), OrderStatus::Pending);
+    // aberta de novo, a disputa de uma ordem sem valor pode ser cancelada
+    t.client
+        .set_order_status(&buyer, &pending, &OrderStatus::Disputed);
+    t.client
+        .set_order_status(&buyer, &pending, &OrderStatus::Cancelled);
+    assert_eq!(status_of(&t, &buyer, pending), OrderStatus::Cancelled);
+
+    // disputa de ordem paga e não creditada termina no reembolso
+    let paid = t.client.buy_order(&buyer, &2, &t.token, &None, &no_terms());
+    t.client
+        .set_order_status(&buyer, &paid, &OrderStatus::Disputed);
+    assert_rejected(&t, &buyer, paid, OrderStatus::Cancelled, Error::EscrowHeld);
+    assert!(!t.client.request_refund(&buyer, &paid));
+    t.client.approve_refund(&t.admin, &buyer, &paid);
+    assert_eq!(status_of(&t, &buyer, paid), OrderStatus::Refunded);
+    assert_eq!(balance(&t, &buyer), 1000);
+}
+
+#[test]
+fn orders_without_time_left_become_exhausted() {
+    let t = setup();
+    add_package(&t, 1, 0, 100);
+    let buyer = funded(&t, 0);
+    let paused = t
+        .client
+        .buy_and_grant(&buyer, &1, &t.token, &None, &no_terms());
+    let started = t
+        .client
+        .buy_and_grant(&buyer, &1, &t.token, &None, &no_terms());
+    t.client.start_order(&buyer, &paused);
+    t.client.start_order(&buyer, &started);
+
+    t.env.ledger().set_timestamp(10_100);
+    // pausar sem saldo esgota a ordem
+    t.client.pause_order(&buyer, &paused);
+    assert_eq!(status_of(&t, &buyer, paused), OrderStatus::Exhausted);
+    // (re)iniciar sem saldo também
+    t.client.start_order(&buyer, &started);
+    assert_eq!(status_of(&t, &buyer, started), OrderStatus::Exhausted);
+    assert_eq!(
+        t.client.try_start_order(&buyer, &started),
+        Err(Ok(Error::InvalidOrderTransition.into()))
+    );
+}
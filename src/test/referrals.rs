@@ -0,0 +1,85 @@
+use super::*;
+use crate::model::{ReferralConfig, ReferralReward, RefundPolicy};
+
+// programa de indicação + reembolso integral do tempo não usado
+fn setup_referrals(reward: ReferralReward) -> (Setup<'static>, Address, Address) {
+    let t = setup();
+    add_package(&t, 1, 1000, 3600);
+    t.client.set_referral_config(&ReferralConfig {
+        reward,
+        min_price: 0,
+        max_rewards_per_referee: 0,
+    });
+    t.client.set_refund_policy(
+        &t.admin,
+        &1,
+        &RefundPolicy {
+            percent_bps: 10_000,
+            min_remaining_secs: 0,
+            fee: 0,
+        },
+    );
+    let referrer = Address::generate(&t.env);
+    let referee = funded(&t, 1000);
+    t.client.register_referrer(&referee, &referrer);
+    (t, referrer, referee)
+}
+
+#[test]
+fn cashback_is_earned_on_credit_paid_by_the_treasury_and_taken_back_on_refund() {
+    let (t, referrer, referee) = setup_referrals(ReferralReward::CashbackBps(1000));
+    let treasury = funded(&t, 0);
+    t.client.set_treasury(&treasury);
+
+    // ordem ainda Pending não rende indicação; a tesouraria recebe tudo
+    let order_id = t
+        .client
+        .buy_order(&referee, &1, &t.token, &None, &no_terms());
+    assert_eq!(balance(&t, &treasury), 1000);
+    assert_eq!(t.client.cashback_due(&referrer, &t.token), 0);
+    assert_eq!(t.client.get_referral_stats(&referrer).rewarded_purchases, 0);
+
+    t.client.grant(&referee, &referee, &order_id);
+    assert_eq!(t.client.cashback_due(&referrer, &t.token), 100);
+    assert_eq!(t.client.pay_cashback(&t.admin, &referrer, &t.token), 100);
+    assert_eq!(balance(&t, &referrer), 100);
+    assert_eq!(balance(&t, &treasury), 900);
+    assert_eq!(t.client.cashback_due(&referrer, &t.token), 0);
+
+    // o reembolso estorna o cashback já pago: o saldo fica devendo
+    StellarAssetClient::new(&t.env, &t.token).mint(&treasury, &100);
+    assert_eq!(t.client.refund_unused(&t.admin, &referee, &order_id), 1000);
+    assert_eq!(t.client.cashback_due(&referrer, &t.token), -100);
+    assert_eq!(
+        t.client
+            .get_referral_stats(&referrer)
+            .cashback
+            .get(t.token.clone()),
+        Some(0)
+    );
+    assert_eq!(t.client.pay_cashback(&t.admin, &referrer, &t.token), 0);
+}
+
+#[test]
+fn bonus_seconds_are_taken_back_on_refund() {
+    let (t, referrer, referee) = setup_referrals(ReferralReward::BonusSecs(600));
+    let order_id = t
+        .client
+        .buy_and_grant(&referee, &1, &t.token, &None, &no_terms());
+    let (bonus_order, _, _) = t.client.get_user_packages(&referrer).get_unchecked(0);
+    assert_eq!(
+        t.client
+            .remaining_by_order(&referrer, &bonus_order, &10_000),
+        600
+    );
+    assert_eq!(t.client.get_session(&referrer).remaining_secs, 600);
+
+    t.client.refund_unused(&t.admin, &referee, &order_id);
+    assert_eq!(
+        t.client
+            .remaining_by_order(&referrer, &bonus_order, &10_000),
+        0
+    );
+    assert_eq!(t.client.get_session(&referrer).remaining_secs, 0);
+    assert_eq!(t.client.get_referral_stats(&referrer).bonus_secs, 0);
+}
@@ -0,0 +1,157 @@
+use super::*;
+use crate::model::{OrderStatus, RefundPolicy, Role};
+
+#[test]
+fn escrowed_order_refunds_itself_after_the_timeout() {
+    let t = setup();
+    add_package(&t, 1, 1000, 3600);
+    t.client.set_escrow_mode(&t.admin, &true);
+    t.client.set_refund_timeout(&t.admin, &600);
+
+    let buyer = funded(&t, 1000);
+    let order_id = t.client.buy_order(&buyer, &1, &t.token, &None, &no_terms());
+
+    // antes do prazo o pedido fica aguardando aprovação
+    assert!(!t.client.request_refund(&buyer, &order_id));
+    assert_eq!(t.client.get_refund_request(&buyer, &order_id), Some(10_000));
+    assert_eq!(balance(&t, &buyer), 0);
+
+    t.env.ledger().set_timestamp(10_600);
+    assert!(t.client.request_refund(&buyer, &order_id));
+    assert_eq!(balance(&t, &buyer), 1000);
+    assert_eq!(balance(&t, &t.client.address), 0);
+    assert_eq!(t.client.get_escrow(&buyer, &order_id), 0);
+    assert_eq!(t.client.get_refund_request(&buyer, &order_id), None);
+    assert_eq!(
+        t.client.get_order(&buyer, &order_id).status,
+        OrderStatus::Refunded
+    );
+    assert_eq!(
+        t.client.try_grant(&buyer, &buyer, &order_id),
+        Err(Ok(Error::OrderRefunded.into()))
+    );
+}
+
+#[test]
+fn approved_refund_is_paid_by_the_treasury_which_co_signs() {
+    let t = setup();
+    add_package(&t, 1, 1000, 3600);
+    let treasury = Address::generate(&t.env);
+    t.client.set_treasury(&treasury);
+    let treasurer = Address::generate(&t.env);
+    t.client.grant_role(&Role::Treasurer, &treasurer);
+
+    let buyer = funded(&t, 1000);
+    let order_id = t.client.buy_order(&buyer, &1, &t.token, &None, &no_terms());
+    assert_eq!(balance(&t, &treasury), 1000);
+
+    assert_eq!(
+        t.client.try_approve_refund(&treasurer, &buyer, &order_id),
+        Err(Ok(Error::RefundNotRequested.into()))
+    );
+    assert!(!t.client.request_refund(&buyer, &order_id));
+
+    t.client.approve_refund(&treasurer, &buyer, &order_id);
+    let signers: std::vec::Vec<Address> = t.env.auths().into_iter().map(|(a, _)| a).collect();
+    assert!(signers.contains(&treasurer));
+    assert!(signers.contains(&treasury));
+    assert_eq!(balance(&t, &treasury), 0);
+    assert_eq!(balance(&t, &buyer), 1000);
+    assert_eq!(
+        t.client.get_order(&buyer, &order_id).status,
+        OrderStatus::Refunded
+    );
+    assert_eq!(
+        t.client.try_grant(&t.admin, &buyer, &order_id),
+        Err(Ok(Error::OrderRefunded.into()))
+    );
+}
+
+fn half_back_minus_fee(t: &Setup, min_remaining_secs: u64, fee: i128) {
+    t.client.set_refund_policy(
+        &t.admin,
+        &1,
+        &RefundPolicy {
+            percent_bps: 5000,
+            min_remaining_secs,
+            fee,
+        },
+    );
+}
+
+#[test]
+fn prorata_refund_of_an_unstarted_order_zeroes_both_sessions() {
+    let t = setup();
+    add_package(&t, 1, 3600, 3600);
+    half_back_minus_fee(&t, 600, 50);
+    let buyer = funded(&t, 3600);
+    let order_id = t
+        .client
+        .buy_and_grant(&buyer, &1, &t.token, &None, &no_terms());
+
+    // 3600 × 3600/3600 × 50% − 50
+    assert_eq!(t.client.refund_unused(&t.admin, &buyer, &order_id), 1750);
+    assert_eq!(balance(&t, &buyer), 1750);
+    let order_session = t.client.get_order_session(&buyer, &order_id);
+    assert_eq!(
+        (order_session.remaining_secs, order_session.started_at),
+        (0, 0)
+    );
+    assert_eq!(t.client.get_session(&buyer).remaining_secs, 0);
+    assert_eq!(
+        t.client.get_order(&buyer, &order_id).status,
+        OrderStatus::Refunded
+    );
+    assert_eq!(
+        t.client.try_refund_unused(&t.admin, &buyer, &order_id),
+        Err(Ok(Error::OrderRefunded.into()))
+    );
+}
+
+#[test]
+fn prorata_refund_follows_the_time_left_and_the_policy() {
+    let t = setup();
+    add_package(&t, 1, 3600, 3600);
+    let buyer = funded(&t, 7200);
+    let first = t
+        .client
+        .buy_and_grant(&buyer, &1, &t.token, &None, &no_terms());
+    let second = t
+        .client
+        .buy_and_grant(&buyer, &1, &t.token, &None, &no_terms());
+    assert_eq!(
+        t.client.try_refund_unused(&t.admin, &buyer, &first),
+        Err(Ok(Error::NoRefundPolicy.into()))
+    );
+    half_back_minus_fee(&t, 600, 50);
+
+    // 900 s usados: 2700 × 50% − 50
+    t.client.start_order(&buyer, &first);
+    t.env.ledger().set_timestamp(10_900);
+    assert_eq!(t.client.refund_unused(&t.admin, &buyer, &first), 1300);
+    let order_session = t.client.get_order_session(&buyer, &first);
+    assert_eq!(
+        (order_session.remaining_secs, order_session.started_at),
+        (0, 0)
+    );
+    // a sessão geral perde os mesmos 2700 s
+    assert_eq!(t.client.get_session(&buyer).remaining_secs, 7200 - 2700);
+
+    // sobram 500 s, abaixo do mínimo da política
+    t.client.start_order(&buyer, &second);
+    t.env.ledger().set_timestamp(10_900 + 3100);
+    assert_eq!(
+        t.client.try_refund_unused(&t.admin, &buyer, &second),
+        Err(Ok(Error::RemainingTooLow.into()))
+    );
+
+    // taxa acima do proporcional: nada volta, mas a ordem é encerrada
+    half_back_minus_fee(&t, 0, 1000);
+    let before = balance(&t, &buyer);
+    assert_eq!(t.client.refund_unused(&t.admin, &buyer, &second), 0);
+    assert_eq!(balance(&t, &buyer), before);
+    assert_eq!(
+        t.client.get_order(&buyer, &second).status,
+        OrderStatus::Refunded
+    );
+}
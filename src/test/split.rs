@@ -0,0 +1,78 @@
+use super::*;
+use crate::model::SplitShare;
+use soroban_sdk::Vec;
+
+fn shares(env: &Env, parts: &[(&Address, u32)]) -> Vec<SplitShare> {
+    let mut shares = Vec::new(env);
+    for (recipient, bps) in parts {
+        shares.push_back(SplitShare {
+            recipient: (*recipient).clone(),
+            bps: *bps,
+        });
+    }
+    shares
+}
+
+#[test]
+fn rounding_dust_goes_to_the_first_recipient() {
+    let t = setup();
+    add_package(&t, 1, 1001, 3600);
+    let [a, b, c] = [0; 3].map(|_| Address::generate(&t.env));
+    t.client.set_split(
+        &None,
+        &shares(&t.env, &[(&a, 3333), (&b, 3333), (&c, 3334)]),
+    );
+
+    let buyer = funded(&t, 1001);
+    t.client
+        .buy_and_grant(&buyer, &1, &t.token, &None, &no_terms());
+    // 333 + 333 + 333 = 999: os 2 restantes vão para o primeiro
+    assert_eq!(balance(&t, &a), 335);
+    assert_eq!(balance(&t, &b), 333);
+    assert_eq!(balance(&t, &c), 333);
+}
+
+#[test]
+fn package_split_overrides_the_global_one_until_cleared() {
+    let t = setup();
+    add_package(&t, 1, 1000, 3600);
+    let [global, isp] = [0; 2].map(|_| Address::generate(&t.env));
+    t.client
+        .set_split(&None, &shares(&t.env, &[(&global, 10_000)]));
+    t.client
+        .set_split(&Some(1), &shares(&t.env, &[(&isp, 10_000)]));
+
+    let buyer = funded(&t, 2000);
+    t.client
+        .buy_and_grant(&buyer, &1, &t.token, &None, &no_terms());
+    assert_eq!((balance(&t, &global), balance(&t, &isp)), (0, 1000));
+
+    t.client.clear_split(&Some(1));
+    t.client
+        .buy_and_grant(&buyer, &1, &t.token, &None, &no_terms());
+    assert_eq!((balance(&t, &global), balance(&t, &isp)), (1000, 1000));
+}
+
+#[test]
+fn invalid_splits_are_rejected() {
+    let t = setup();
+    let a = Address::generate(&t.env);
+    let b = Address::generate(&t.env);
+    // 11 partes que somam 10000
+    let too_many: std::vec::Vec<(&Address, u32)> = (0..11)
+        .map(|i| (&a, if i == 0 { 1000 } else { 900 }))
+        .collect();
+    for bad in [
+        shares(&t.env, &[]),
+        shares(&t.env, &[(&a, 6000), (&b, 3000)]),
+        shares(&t.env, &[(&a, 6000), (&b, 5000)]),
+        shares(&t.env, &[(&a, 10_000), (&b, 0)]),
+        shares(&t.env, &too_many),
+    ] {
+        assert_eq!(
+            t.client.try_set_split(&None, &bad),
+            Err(Ok(Error::InvalidSplit.into()))
+        );
+    }
+    assert!(t.client.get_split(&None).is_empty());
+}
@@ -0,0 +1,117 @@
+use super::*;
+use crate::model::{OrderStatus, TrialGate};
+use ed25519_dalek::{Signer, SigningKey};
+use soroban_sdk::xdr::ToXdr;
+use soroban_sdk::{BytesN, vec};
+
+fn add_trial_package(t: &Setup) {
+    add_package(t, 9, 0, 900);
+    t.client.set_package_trial(&t.admin, &9, &true);
+}
+
+// assinatura do atestador sobre (contrato, owner), como `claim_trial` verifica
+fn attest(t: &Setup, key: &SigningKey, owner: &Address) -> BytesN<64> {
+    let message = (t.client.address.clone(), owner.clone()).to_xdr(&t.env);
+    let mut buf = [0u8; 128];
+    let len = message.len() as usize;
+    message.copy_into_slice(&mut buf[..len]);
+    BytesN::from_array(&t.env, &key.sign(&buf[..len]).to_bytes())
+}
+
+#[test]
+fn trial_is_claimed_once_per_address() {
+    let t = setup();
+    add_trial_package(&t);
+    let user = Address::generate(&t.env);
+
+    let order_id = t.client.claim_trial(&user, &9, &None);
+    let order = t.client.get_order(&user, &order_id);
+    assert_eq!((order.status, order.price_paid), (OrderStatus::Credited, 0));
+    assert_eq!(t.client.remaining_by_order(&user, &order_id, &10_000), 900);
+    assert!(t.client.has_claimed_trial(&user));
+    assert_eq!(
+        t.client.try_claim_trial(&user, &9, &None),
+        Err(Ok(Error::TrialAlreadyClaimed.into()))
+    );
+}
+
+#[test]
+fn trial_packages_stay_free_and_out_of_sale() {
+    let t = setup();
+    add_trial_package(&t);
+    add_package(&t, 1, 1000, 3600);
+    assert_eq!(
+        t.client.try_set_package_trial(&t.admin, &1, &true),
+        Err(Ok(Error::TrialPackageMismatch.into()))
+    );
+    assert_eq!(
+        t.client.try_set_package(
+            &t.admin,
+            &9,
+            &100,
+            &900,
+            &package_text(&t.env, "Teste"),
+            &false
+        ),
+        Err(Ok(Error::TrialPackageMismatch.into()))
+    );
+    let buyer = funded(&t, 1000);
+    assert_eq!(
+        t.client
+            .try_buy_order(&buyer, &9, &t.token, &None, &no_terms()),
+        Err(Ok(Error::TrialPackageMismatch.into()))
+    );
+    assert_eq!(
+        t.client.try_claim_trial(&buyer, &1, &None),
+        Err(Ok(Error::TrialPackageMismatch.into()))
+    );
+}
+
+#[test]
+fn allowlist_gate_only_admits_listed_addresses() {
+    let t = setup();
+    add_trial_package(&t);
+    t.client.set_trial_gate(&TrialGate::Allowlist);
+    let listed = Address::generate(&t.env);
+    let other = Address::generate(&t.env);
+    t.client
+        .set_trial_allowed(&vec![&t.env, listed.clone()], &true);
+
+    assert_eq!(
+        t.client.try_claim_trial(&other, &9, &None),
+        Err(Ok(Error::Unauthorized.into()))
+    );
+    t.client.claim_trial(&listed, &9, &None);
+    assert!(t.client.has_claimed_trial(&listed));
+    assert!(!t.client.has_claimed_trial(&other));
+}
+
+#[test]
+fn attestation_gate_requires_the_signer_over_this_owner() {
+    let t = setup();
+    add_trial_package(&t);
+    let key = SigningKey::from_bytes(&[7; 32]);
+    t.client
+        .set_trial_gate(&TrialGate::Attestation(BytesN::from_array(
+            &t.env,
+            &key.verifying_key().to_bytes(),
+        )));
+    let user = Address::generate(&t.env);
+    let other = Address::generate(&t.env);
+
+    assert_eq!(
+        t.client.try_claim_trial(&user, &9, &None),
+        Err(Ok(Error::Unauthorized.into()))
+    );
+    // atestado de outro endereço não serve
+    let signature = attest(&t, &key, &user);
+    assert!(
+        t.client
+            .try_claim_trial(&other, &9, &Some(signature.clone()))
+            .is_err()
+    );
+    assert!(!t.client.has_claimed_trial(&other));
+
+    t.client.claim_trial(&user, &9, &Some(signature));
+    assert!(t.client.has_claimed_trial(&user));
+}